@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_atmosphere::prelude::AtmosphereCamera;
 use bevy_mod_raycast::prelude::*;
 
+use crate::player::{CurrentPlayerMarker, Player};
 use crate::GameState;
 
 #[derive(TypePath)]
@@ -26,7 +27,24 @@ impl Default for CameraController {
     }
 }
 
-pub fn spawn_camera(mut commands: Commands) {
+impl CameraController {
+    /// Sets the angles so the camera looks towards `direction`
+    pub fn look_towards(&mut self, direction: Vec3) {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return;
+        }
+        self.angle_x = f32::atan2(-direction.x, -direction.z);
+        self.angle_y = -direction.y.clamp(-1.0, 1.0).asin();
+    }
+}
+
+pub fn spawn_camera(mut commands: Commands, player: Query<&Player, With<CurrentPlayerMarker>>) {
+    let mut controller = CameraController::default();
+    if let Ok(player) = player.get_single() {
+        controller.look_towards(player.spawn_direction);
+    }
+
     commands
         .spawn(Camera3dBundle {
             transform: Transform::from_translation(Vec3::new(0.0, 5.0, 10.0))
@@ -37,7 +55,7 @@ pub fn spawn_camera(mut commands: Commands) {
             }),
             ..Default::default()
         })
-        .insert(CameraController::default()) // Ajoute le CameraController
+        .insert(controller) // Ajoute le CameraController
         .insert({
             let mut raycast_source = RaycastSource::<BlockRaycastSet>::default(); // Initialisation par défaut
             raycast_source.cast_method = RaycastMethod::Transform; // Utilise la transformation de la caméra pour lancer le rayon
@@ -1,20 +1,19 @@
 pub const CUBE_SIZE: f32 = 1.0;
-pub const GRAVITY: f32 = -9.8 * 4.0;
 
 pub const TEXTURE_SIZE: u32 = 16;
 
-pub const INTERACTION_DISTANCE: f32 = 7.;
 pub const BASE_ROUGHNESS: f32 = 0.6;
 pub const BASE_SPECULAR_HIGHLIGHT: f32 = 0.;
 
 // increase render distance if we build the project in release mode
 pub const DEFAULT_CHUNK_RENDER_DISTANCE_RADIUS: u32 = if cfg!(debug_assertions) { 2 } else { 4 };
+// chunks loaded above and below the player
+pub const DEFAULT_CHUNK_VERTICAL_RENDER_DISTANCE_RADIUS: u32 =
+    if cfg!(debug_assertions) { 2 } else { 3 };
 
 pub const CELESTIAL_SIZE: f32 = 10.;
 pub const CELESTIAL_DISTANCE: f32 = 50.; // Low value for testing ; will be increased later
-pub const DAY_DURATION: f32 = 60.;
 
-pub const MAX_INVENTORY_SLOTS: u32 = 4 * 9;
 pub const MAX_HOTBAR_SLOTS: u32 = 9;
 
 pub const HOTBAR_CELL_SIZE: f32 = 50.;
@@ -25,7 +24,5 @@ pub const SAVE_PATH: &str = "saves/";
 pub const SERVER_LIST_SAVE_NAME: &str = "servers.ron";
 pub const BINDS_PATH: &str = "keybindings.ron";
 
-pub const GRASS_COLOR: [f32; 4] = [0.1, 1.0, 0.3, 1.0];
-
 pub const TEXTURE_PATH_BASE: &str = "graphics/base_textures/";
 pub const TEXTURE_PATH_CUSTOM: &str = "graphics/custom_textures/";
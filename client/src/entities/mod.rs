@@ -0,0 +1,108 @@
+mod models;
+
+pub use models::*;
+
+use crate::world::MaterialResource;
+use crate::GameState;
+use bevy::prelude::*;
+use shared::entities::{EntityId, EntityMotion};
+use shared::messages::EntityMessage;
+use std::collections::HashMap;
+
+/// How quickly the entities catch up with the positions sent by the server
+const FOLLOW_SPEED: f32 = 15.0;
+
+/// Bevy entities of the entities sent by the server
+#[derive(Resource, Default)]
+pub struct ClientEntities {
+    entities: HashMap<EntityId, Entity>,
+}
+
+/// Entity replicated from the server. Its model is one of its children
+#[derive(Component)]
+pub struct ReplicatedEntity {
+    /// Last motion sent by the server
+    pub target: EntityMotion,
+}
+
+fn entity_transform(motion: &EntityMotion) -> Transform {
+    Transform::from_translation(motion.position)
+        .with_rotation(Quat::from_rotation_y(motion.rotation))
+}
+
+pub fn apply_entity_messages(
+    mut commands: Commands,
+    mut events: EventReader<EntityMessage>,
+    mut client_entities: ResMut<ClientEntities>,
+    (mut models, mut meshes, materials, mut colors): (
+        ResMut<EntityModels>,
+        ResMut<Assets<Mesh>>,
+        Res<MaterialResource>,
+        ResMut<Assets<StandardMaterial>>,
+    ),
+    mut entities_query: Query<&mut ReplicatedEntity>,
+) {
+    for message in events.read() {
+        match message {
+            EntityMessage::Spawn(spawned) => {
+                for entity in spawned.iter() {
+                    // Sent again when coming back into view
+                    if let Some(previous) = client_entities.entities.remove(&entity.id) {
+                        commands.entity(previous).despawn_recursive();
+                    }
+                    let id = commands
+                        .spawn((
+                            StateScoped(GameState::Game),
+                            SpatialBundle::from_transform(entity_transform(&entity.motion)),
+                            ReplicatedEntity {
+                                target: entity.motion,
+                            },
+                        ))
+                        .with_children(|parent| {
+                            spawn_model(
+                                parent,
+                                &entity.kind,
+                                &mut models,
+                                &mut meshes,
+                                &materials,
+                                &mut colors,
+                            )
+                        })
+                        .id();
+                    client_entities.entities.insert(entity.id, id);
+                }
+            }
+            EntityMessage::Update(updated) => {
+                for (id, motion) in updated.iter() {
+                    if let Some(mut entity) = client_entities
+                        .entities
+                        .get(id)
+                        .and_then(|entity| entities_query.get_mut(*entity).ok())
+                    {
+                        entity.target = *motion;
+                    }
+                }
+            }
+            EntityMessage::Despawn(despawned) => {
+                for id in despawned.iter() {
+                    if let Some(entity) = client_entities.entities.remove(id) {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Smoothes the movement of the entities between two updates of the server
+pub fn interpolate_entities(
+    time: Res<Time>,
+    mut entities_query: Query<(&ReplicatedEntity, &mut Transform)>,
+) {
+    let follow = 1. - (-FOLLOW_SPEED * time.delta_seconds()).exp();
+    for (entity, mut transform) in entities_query.iter_mut() {
+        let target = entity_transform(&entity.target);
+        transform.translation = transform.translation.lerp(target.translation, follow);
+        transform.rotation = transform.rotation.slerp(target.rotation, follow);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::world::meshing::textured_cuboid;
+use crate::world::MaterialResource;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use shared::entities::{EntityKind, MobKind};
+use shared::world::{registry, ItemId, ItemType};
+use std::collections::HashMap;
+
+/// Size of the items that are blocks, drawn as small cubes
+const BLOCK_ITEM_SIZE: Vec3 = Vec3::splat(0.25);
+/// Size of the other items, drawn as thin plates
+const FLAT_ITEM_SIZE: Vec3 = Vec3::new(0.4, 0.4, 0.03);
+/// Rotation speed of the items lying in the world, in radians per second
+const ITEM_SPIN_SPEED: f32 = 1.5;
+
+/// Part of the model of a mob: a colored box, placed relative to the center of the mob
+struct ModelPart {
+    size: Vec3,
+    offset: Vec3,
+    color: Color,
+}
+
+const fn part(size: Vec3, offset: Vec3, color: Color) -> ModelPart {
+    ModelPart {
+        size,
+        offset,
+        color,
+    }
+}
+
+const SHEEP_WOOL: Color = Color::srgb(0.92, 0.92, 0.88);
+const SHEEP_SKIN: Color = Color::srgb(0.35, 0.28, 0.24);
+const ZOMBIE_SKIN: Color = Color::srgb(0.33, 0.55, 0.3);
+const ZOMBIE_SHIRT: Color = Color::srgb(0.2, 0.6, 0.65);
+const ZOMBIE_PANTS: Color = Color::srgb(0.25, 0.25, 0.55);
+
+/// Parts of the model of a mob, which faces +Z
+fn mob_parts(kind: MobKind) -> Vec<ModelPart> {
+    match kind {
+        MobKind::Sheep => vec![
+            part(
+                Vec3::new(0.7, 0.6, 0.9),
+                Vec3::new(0., 0.05, 0.),
+                SHEEP_WOOL,
+            ),
+            part(Vec3::splat(0.4), Vec3::new(0., 0.3, 0.55), SHEEP_SKIN),
+            part(
+                Vec3::new(0.2, 0.4, 0.2),
+                Vec3::new(0.2, -0.45, 0.3),
+                SHEEP_SKIN,
+            ),
+            part(
+                Vec3::new(0.2, 0.4, 0.2),
+                Vec3::new(-0.2, -0.45, 0.3),
+                SHEEP_SKIN,
+            ),
+            part(
+                Vec3::new(0.2, 0.4, 0.2),
+                Vec3::new(0.2, -0.45, -0.3),
+                SHEEP_SKIN,
+            ),
+            part(
+                Vec3::new(0.2, 0.4, 0.2),
+                Vec3::new(-0.2, -0.45, -0.3),
+                SHEEP_SKIN,
+            ),
+        ],
+        MobKind::Zombie => vec![
+            part(
+                Vec3::new(0.25, 0.75, 0.25),
+                Vec3::new(0.13, -0.525, 0.),
+                ZOMBIE_PANTS,
+            ),
+            part(
+                Vec3::new(0.25, 0.75, 0.25),
+                Vec3::new(-0.13, -0.525, 0.),
+                ZOMBIE_PANTS,
+            ),
+            part(
+                Vec3::new(0.5, 0.7, 0.25),
+                Vec3::new(0., 0.2, 0.),
+                ZOMBIE_SHIRT,
+            ),
+            part(Vec3::splat(0.45), Vec3::new(0., 0.675, 0.), ZOMBIE_SKIN),
+            // Arms stretched forward
+            part(
+                Vec3::new(0.2, 0.2, 0.6),
+                Vec3::new(0.35, 0.4, 0.3),
+                ZOMBIE_SKIN,
+            ),
+            part(
+                Vec3::new(0.2, 0.2, 0.6),
+                Vec3::new(-0.35, 0.4, 0.3),
+                ZOMBIE_SKIN,
+            ),
+        ],
+    }
+}
+
+/// Meshes and materials of the models, created the first time they are needed
+#[derive(Resource, Default)]
+pub struct EntityModels {
+    items: HashMap<ItemId, Handle<Mesh>>,
+    /// Mesh, material and offset of each part of the mobs
+    mobs: HashMap<MobKind, Vec<(Handle<Mesh>, Handle<StandardMaterial>, Vec3)>>,
+}
+
+/// Models turning on themselves, whatever their entity does
+#[derive(Component)]
+pub struct SpinningModel {
+    /// In radians per second
+    speed: f32,
+}
+
+fn item_mesh(item_id: ItemId, materials: &MaterialResource) -> Option<Mesh> {
+    let uvs = materials.items.uvs.get(&registry().item(item_id).texture)?;
+    let size = match item_id.get_default_type() {
+        ItemType::Block(_) => BLOCK_ITEM_SIZE,
+        _ => FLAT_ITEM_SIZE,
+    };
+    Some(textured_cuboid(size, uvs))
+}
+
+/// Spawns the model of an entity of the given kind, as a child of the entity
+pub fn spawn_model(
+    parent: &mut ChildBuilder,
+    kind: &EntityKind,
+    models: &mut EntityModels,
+    meshes: &mut Assets<Mesh>,
+    materials: &MaterialResource,
+    colors: &mut Assets<StandardMaterial>,
+) {
+    match kind {
+        EntityKind::DroppedItem(item_id) => {
+            let mesh = match models.items.get(item_id) {
+                Some(mesh) => mesh.clone(),
+                None => {
+                    let Some(mesh) = item_mesh(*item_id, materials) else {
+                        warn!("No texture found for the dropped item {:?}", item_id);
+                        return;
+                    };
+                    let mesh = meshes.add(mesh);
+                    models.items.insert(*item_id, mesh.clone());
+                    mesh
+                }
+            };
+            parent.spawn((
+                NotShadowCaster,
+                PbrBundle {
+                    mesh,
+                    material: materials.items.material.clone().unwrap(),
+                    ..Default::default()
+                },
+                SpinningModel {
+                    speed: ITEM_SPIN_SPEED,
+                },
+            ));
+        }
+        EntityKind::Mob(mob_kind) => {
+            let parts = models.mobs.entry(*mob_kind).or_insert_with(|| {
+                mob_parts(*mob_kind)
+                    .into_iter()
+                    .map(|part| {
+                        (
+                            meshes.add(Cuboid::from_size(part.size)),
+                            colors.add(part.color),
+                            part.offset,
+                        )
+                    })
+                    .collect()
+            });
+            for (mesh, material, offset) in parts.iter() {
+                parent.spawn(PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(*offset),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+pub fn spin_models(time: Res<Time>, mut models_query: Query<(&SpinningModel, &mut Transform)>) {
+    for (model, mut transform) in models_query.iter_mut() {
+        transform.rotate_y(model.speed * time.delta_seconds());
+    }
+}
@@ -3,8 +3,8 @@ use std::collections::HashMap;
 use crate::ui::chat::{render_chat, setup_chat};
 use bevy::prelude::*;
 use bevy_atmosphere::prelude::*;
-use inventory::Inventory;
-use shared::messages::PlayerSpawnEvent;
+use shared::messages::{EntityMessage, PlayerSpawnEvent};
+use shared::players::Inventory;
 
 use crate::world::time::ClientTime;
 use crate::world::ClientWorldMap;
@@ -27,17 +27,19 @@ use crate::ui::set_ui_mode;
 use crate::world::*;
 
 use crate::camera::*;
+use crate::entities::*;
 use crate::input::*;
 use crate::player::*;
 use crate::ui::inventory::*;
+use shared::players::SIMULATION_TICK_RATE;
 use shared::world::{BlockId, ItemId, WorldSeed};
 
 use crate::menu::game_loading_screen::load_loading_screen;
 use crate::network::{
-    establish_authenticated_connection_to_server, init_server_connection,
-    launch_local_server_system, network_failure_handler, poll_network_messages,
-    send_player_position_to_server, terminate_server_connection, upload_player_inputs_system,
-    CurrentPlayerProfile, TargetServer, TargetServerState,
+    establish_authenticated_connection_to_server, handle_server_disconnection,
+    init_server_connection, launch_local_server_system, network_failure_handler,
+    poll_network_messages, terminate_server_connection, CurrentPlayerProfile, TargetServer,
+    TargetServerState,
 };
 use crate::{DisplayQuality, GameState, Volume};
 
@@ -85,12 +87,19 @@ pub fn game_plugin(app: &mut App) {
         .insert_resource(AtlasHandles::<ItemId>::default())
         .insert_resource(RenderDistance { ..default() })
         .insert_resource(UIMode::Closed)
+        .init_resource::<OpenedCraftingTable>()
+        .init_resource::<BlockBreaking>()
+        .init_resource::<ClientEntities>()
+        .init_resource::<EntityModels>()
         .insert_resource(ViewMode::FirstPerson)
         .insert_resource(DebugOptions::default())
         .insert_resource(Inventory::new())
         .insert_resource(CurrentPlayerProfile::new())
+        .insert_resource(PlayerPrediction::default())
+        .insert_resource(Time::<Fixed>::from_hz(SIMULATION_TICK_RATE))
         .add_event::<WorldRenderRequestUpdateEvent>()
         .add_event::<PlayerSpawnEvent>()
+        .add_event::<EntityMessage>()
         .add_systems(
             OnEnter(GameState::PreGameLoading),
             (
@@ -105,6 +114,7 @@ pub fn game_plugin(app: &mut App) {
             Update,
             (
                 establish_authenticated_connection_to_server,
+                handle_server_disconnection,
                 create_all_atlases,
                 check_pre_loading_complete,
                 spawn_player,
@@ -129,7 +139,7 @@ pub fn game_plugin(app: &mut App) {
         )
         .add_systems(
             OnEnter(GameState::Game),
-            (print_settings, setup_chunk_ghost),
+            (print_settings, setup_chunk_ghost, setup_breaking_overlay),
         )
         .add_systems(
             Update,
@@ -137,6 +147,7 @@ pub fn game_plugin(app: &mut App) {
                 render_pause_menu,
                 render_chat,
                 render_inventory_hotbar,
+                update_crafting_grid,
                 set_ui_mode,
             )
                 .run_if(in_state(GameState::Game)),
@@ -145,8 +156,14 @@ pub fn game_plugin(app: &mut App) {
             Update,
             (
                 render_distance_update_system,
-                player_movement_system,
-                (handle_block_interactions, camera_control_system).chain(),
+                player_controls_system,
+                (
+                    handle_block_interactions,
+                    update_breaking_overlay,
+                    drop_selected_item,
+                    camera_control_system,
+                )
+                    .chain(),
                 fps_text_update_system,
                 coords_text_update_system,
                 total_blocks_text_update_system,
@@ -161,6 +178,10 @@ pub fn game_plugin(app: &mut App) {
                 .chain()
                 .run_if(in_state(GameState::Game)),
         )
+        .add_systems(
+            FixedUpdate,
+            player_movement_system.run_if(in_state(GameState::Game)),
+        )
         .add_systems(
             PostUpdate,
             (world_render_system).run_if(in_state(GameState::Game)),
@@ -169,10 +190,10 @@ pub fn game_plugin(app: &mut App) {
             Update,
             (
                 poll_network_messages,
+                handle_server_disconnection,
                 network_failure_handler,
-                upload_player_inputs_system,
-                send_player_position_to_server,
                 spawn_player,
+                (apply_entity_messages, interpolate_entities, spin_models).chain(),
             )
                 .run_if(in_state(GameState::Game)),
         )
@@ -182,7 +203,17 @@ pub fn game_plugin(app: &mut App) {
         );
 }
 
-fn clear_resources(mut world_map: ResMut<ClientWorldMap>) {
+fn clear_resources(
+    mut world_map: ResMut<ClientWorldMap>,
+    mut prediction: ResMut<PlayerPrediction>,
+    mut inventory: ResMut<Inventory>,
+    mut breaking: ResMut<BlockBreaking>,
+    mut client_entities: ResMut<ClientEntities>,
+) {
+    *prediction = PlayerPrediction::default();
+    *inventory = Inventory::new();
+    *breaking = BlockBreaking::default();
+    *client_entities = ClientEntities::default();
     world_map.map = HashMap::new();
     world_map.total_blocks_count = 0;
     world_map.total_chunks_count = 0;
@@ -19,6 +19,7 @@ pub enum GameAction {
     FlyDown,
     ToggleBlockWireframeDebugMode,
     ToggleInventory,
+    DropItem,
     OpenChat,
     RenderDistanceMinus,
     RenderDistancePlus,
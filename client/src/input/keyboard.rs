@@ -107,6 +107,7 @@ pub fn get_bindings(game_folder_path: &String) -> KeyMap {
             map.insert(GameAction::FlyDown, vec![KeyCode::ShiftLeft]);
             map.insert(GameAction::ToggleBlockWireframeDebugMode, vec![KeyCode::F6]);
             map.insert(GameAction::ToggleInventory, vec![KeyCode::KeyE]);
+            map.insert(GameAction::DropItem, vec![KeyCode::KeyQ]);
             map.insert(GameAction::OpenChat, vec![KeyCode::KeyT]);
             map.insert(GameAction::RenderDistanceMinus, vec![KeyCode::KeyO]);
             map.insert(GameAction::RenderDistancePlus, vec![KeyCode::KeyP]);
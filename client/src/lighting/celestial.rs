@@ -3,13 +3,14 @@ use crate::world::materials::MaterialResource;
 use crate::world::time::ClientTime;
 use crate::GameState;
 use crate::{
-    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE, DAY_DURATION},
+    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE},
     world::GlobalMaterial,
 };
 use bevy::{
     pbr::{NotShadowCaster, NotShadowReceiver},
     prelude::*,
 };
+use shared::DAY_DURATION;
 use std::f32::consts::PI;
 
 //
@@ -2,6 +2,7 @@
 
 mod camera;
 mod constants;
+mod entities;
 mod game;
 mod input;
 mod lighting;
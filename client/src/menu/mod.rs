@@ -7,6 +7,7 @@ use controls::{controls_menu_setup, controls_update_system};
 use multi::multiplayer_action;
 
 use crate::input::keyboard::save_keybindings;
+use crate::network::DisconnectReason;
 use crate::{DisplayQuality, GameState, MenuCamera, Volume, TEXT_COLOR};
 
 pub mod controls;
@@ -162,7 +163,11 @@ fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>, mut commands: Comman
     menu_state.set(MenuState::Main);
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+) {
     // Common style for all buttons on the screen
     let button_style = Style {
         width: Val::Px(300.0),
@@ -228,6 +233,29 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
+                    // Tell the player why they were sent back here, only once
+                    if let Some(reason) = disconnect_reason.0.take() {
+                        parent.spawn(
+                            TextBundle::from_section(
+                                reason,
+                                TextStyle {
+                                    font_size: 22.0,
+                                    color: TEXT_COLOR,
+                                    ..Default::default()
+                                },
+                            )
+                            .with_style(Style {
+                                margin: UiRect::new(
+                                    Val::Px(20.0),
+                                    Val::Px(20.0),
+                                    Val::Px(0.0),
+                                    Val::Px(20.0),
+                                ),
+                                ..Default::default()
+                            }),
+                        );
+                    }
+
                     // Display three buttons for each action available from the main menu:
                     // - new game
                     // - settings
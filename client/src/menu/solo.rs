@@ -247,21 +247,37 @@ pub fn list_worlds(
 
     let paths = fs::read_dir(path).unwrap();
 
+    // Worlds are stored in their own folder, legacy saves are single `.ron` files
+    // that will be converted by the server when loaded
+    let mut names: Vec<String> = Vec::new();
     for path in paths {
-        let path_str = path.unwrap().file_name().into_string().unwrap();
-
-        if path_str.ends_with(".ron") {
-            add_world_item(
-                path_str.replace(".ron", ""),
-                &mut commands,
-                &assets,
-                &mut list,
-                list_entity,
-                &mut world_map,
-                &game_paths,
-            );
+        let entry = path.unwrap();
+        let path_str = entry.file_name().into_string().unwrap();
+
+        let name = if entry.path().is_dir() {
+            path_str
+        } else if path_str.ends_with(".ron") {
+            path_str.replace(".ron", "")
+        } else {
+            continue;
+        };
+
+        if !names.contains(&name) {
+            names.push(name);
         }
     }
+
+    for name in names {
+        add_world_item(
+            name,
+            &mut commands,
+            &assets,
+            &mut list,
+            list_entity,
+            &mut world_map,
+            &game_paths,
+        );
+    }
 }
 
 fn add_world_item(
@@ -480,20 +496,23 @@ pub fn delete_save_files(
     world_name: &str,
     game_folder_path: &Res<GameFolderPaths>,
 ) -> Result<(), io::Error> {
-    // Delete `world_save.ron`
-    match fs::remove_file(format!(
-        "{}{}.ron",
-        get_game_folder(Some(&game_folder_path))
-            .join(SAVE_PATH)
-            .display(),
-        world_name
-    )) {
+    let save_path: PathBuf = get_game_folder(Some(game_folder_path)).join(SAVE_PATH);
+
+    // Delete the world folder (metadata and region files)
+    match fs::remove_dir_all(save_path.join(world_name)) {
         Ok(_) => info!("Successfully deleted world"),
         Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
-            error!("world_save.ron not found, skipping.")
+            error!("world folder not found, skipping.")
         }
         Err(e) => error!("Failed to delete world: {}", e),
     }
 
+    // Delete legacy `world_save.ron`, if it was never converted
+    match fs::remove_file(save_path.join(format!("{}.ron", world_name))) {
+        Ok(_) => info!("Successfully deleted legacy world save"),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => error!("Failed to delete legacy world save: {}", e),
+    }
+
     Ok(())
 }
@@ -1,75 +1,104 @@
 use bevy::{math::IVec3, prelude::ResMut};
 use bevy_renet::renet::{DefaultChannel, RenetClient};
-use bincode::Options;
-use shared::messages::{ChatMessage, ClientToServerMessage, SaveWorldRequest};
+use shared::messages::{encode_message, ClientToServerMessage, InventoryLayout, SaveWorldRequest};
 use shared::world::BlockData;
 
 pub enum NetworkAction {
     ChatMessage(String),
     WorldUpdateRequest {
-        requested_chunks: Vec<IVec3>,
-        player_chunk_pos: IVec3,
         render_distance: u32,
+        vertical_render_distance: u32,
+    },
+    SaveWorldRequest {
+        session_token: u128,
+    },
+    StartBreaking {
+        position: IVec3,
+    },
+    AbortBreaking {
+        position: IVec3,
     },
-    SaveWorldRequest,
     BlockInteraction {
         position: IVec3,
         block_type: Option<BlockData>, // None = suppression, Some = ajout
+        slot: u32,
+    },
+    InventoryLayout(InventoryLayout),
+    CraftItem {
+        table: Option<IVec3>,
+    },
+    DropItem {
+        slot: u32,
     },
+    DebugGetBlocks,
 }
 
 pub fn send_network_action(client: &mut ResMut<RenetClient>, action: NetworkAction) {
     match action {
         NetworkAction::ChatMessage(msg) => {
-            let timestamp_ms = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64;
-            let input_message = bincode::options()
-                .serialize(&ClientToServerMessage::ChatMessage(ChatMessage {
-                    author_name: "User".into(),
-                    content: msg,
-                    date: timestamp_ms,
-                }))
-                .unwrap();
+            let input_message = encode_message(&ClientToServerMessage::ChatMessage(msg));
 
             client.send_message(DefaultChannel::ReliableOrdered, input_message);
         }
         NetworkAction::WorldUpdateRequest {
-            requested_chunks,
-            player_chunk_pos,
             render_distance,
+            vertical_render_distance,
         } => {
-            let input_message = bincode::options()
-                .serialize(&ClientToServerMessage::WorldUpdateRequest {
-                    player_chunk_position: player_chunk_pos,
-                    requested_chunks,
-                    render_distance,
-                })
-                .unwrap();
+            let input_message = encode_message(&ClientToServerMessage::WorldUpdateRequest {
+                render_distance,
+                vertical_render_distance,
+            });
 
             client.send_message(DefaultChannel::ReliableOrdered, input_message);
         }
-        NetworkAction::SaveWorldRequest => {
+        NetworkAction::SaveWorldRequest { session_token } => {
             let save_request =
-                ClientToServerMessage::SaveWorldRequest(SaveWorldRequest { session_token: 0 });
+                ClientToServerMessage::SaveWorldRequest(SaveWorldRequest { session_token });
 
-            let input_message = bincode::options()
-                .serialize(&save_request)
-                .expect("Failed to serialize SaveWorldRequest");
+            let input_message = encode_message(&save_request);
 
             client.send_message(DefaultChannel::ReliableOrdered, input_message);
         }
+        NetworkAction::StartBreaking { position } => {
+            let message = encode_message(&ClientToServerMessage::StartBreaking { position });
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
+        NetworkAction::AbortBreaking { position } => {
+            let message = encode_message(&ClientToServerMessage::AbortBreaking { position });
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
         NetworkAction::BlockInteraction {
             position,
             block_type,
+            slot,
         } => {
-            let message = bincode::options()
-                .serialize(&ClientToServerMessage::BlockInteraction {
-                    position,
-                    block_type,
-                })
-                .unwrap();
+            let message = encode_message(&ClientToServerMessage::BlockInteraction {
+                position,
+                block_type,
+                slot,
+            });
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
+        NetworkAction::InventoryLayout(layout) => {
+            let message = encode_message(&ClientToServerMessage::InventoryLayout(layout));
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
+        NetworkAction::CraftItem { table } => {
+            let message = encode_message(&ClientToServerMessage::CraftItem { table });
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
+        NetworkAction::DropItem { slot } => {
+            let message = encode_message(&ClientToServerMessage::DropItem { slot });
+
+            client.send_message(DefaultChannel::ReliableOrdered, message);
+        }
+        NetworkAction::DebugGetBlocks => {
+            let message = encode_message(&ClientToServerMessage::DebugGetBlocks);
 
             client.send_message(DefaultChannel::ReliableOrdered, message);
         }
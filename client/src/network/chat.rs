@@ -1,29 +1,33 @@
 use crate::network::api::{send_network_action, NetworkAction};
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
-use shared::messages::ChatConversation;
+use shared::messages::{ChatMessage, ChatMessageId, ChatUpdate, CHAT_HISTORY_LENGTH};
+use std::collections::VecDeque;
 
 #[derive(Resource, Default, Debug)]
 pub struct CachedChatConversation {
-    pub last_update: u64,
-    pub data: Option<ChatConversation>,
+    /// Latest messages, public ones as well as the ones sent to this player only
+    pub messages: VecDeque<ChatMessage>,
+    /// Last message shown on screen
+    pub last_rendered_id: Option<ChatMessageId>,
 }
 
 pub fn send_chat_message(client: &mut ResMut<RenetClient>, msg: &str) {
     send_network_action(client, NetworkAction::ChatMessage(msg.into()));
 }
 
-pub fn update_cached_chat_state(
-    chat_state: &mut ResMut<CachedChatConversation>,
-    new_state: ChatConversation,
-) {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
-
-    chat_state.last_update = now;
-    chat_state.data = Some(new_state);
+pub fn update_cached_chat_state(chat_state: &mut CachedChatConversation, update: ChatUpdate) {
+    match update {
+        ChatUpdate::Backlog(conversation) => {
+            chat_state.messages = conversation.messages;
+        }
+        ChatUpdate::Message(message) => {
+            chat_state.messages.push_back(message);
+        }
+    }
+    while chat_state.messages.len() > CHAT_HISTORY_LENGTH {
+        chat_state.messages.pop_front();
+    }
 
     trace!("new CachedChatConversation: {:?}", &chat_state);
 }
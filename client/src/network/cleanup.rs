@@ -1,8 +1,20 @@
 use crate::network::{TargetServer, TargetServerState};
+use crate::GameState;
 use bevy::prelude::*;
 use bevy_renet::renet::{DefaultChannel, RenetClient};
-use bincode::Options;
-use shared::messages::{ClientToServerMessage, ExitOrder};
+use shared::messages::{encode_message, ClientToServerMessage, ExitOrder};
+
+/// Why the last connection to a server ended, shown once back in the menu
+#[derive(Resource, Default, Debug)]
+pub struct DisconnectReason(pub Option<String>);
+
+fn reset_target(target: &mut TargetServer) {
+    target.address = None;
+    target.username = None;
+    target.session_token = None;
+    target.connect_token = None;
+    target.state = TargetServerState::Initial;
+}
 
 pub fn terminate_server_connection(
     mut client: ResMut<RenetClient>,
@@ -12,11 +24,46 @@ pub fn terminate_server_connection(
     let order = ClientToServerMessage::Exit(ExitOrder {
         session_token: target.session_token.unwrap_or_default(),
     });
-    let payload = bincode::options().serialize(&order).unwrap();
+    let payload = encode_message(&order);
     client.send_message(DefaultChannel::ReliableOrdered, payload);
 
-    target.address = None;
-    target.username = None;
-    target.session_token = None;
-    target.state = TargetServerState::Initial;
+    reset_target(&mut target);
+}
+
+/// Leaves the server, the reason is kept for the menu
+pub fn disconnect_with_reason(
+    client: &mut RenetClient,
+    disconnect_reason: &mut DisconnectReason,
+    reason: String,
+) {
+    error!("Disconnecting from the server: {}", reason);
+    disconnect_reason.0 = Some(reason);
+    client.disconnect();
+}
+
+/// Goes back to the menu once the connection is lost, whatever the cause
+pub fn handle_server_disconnection(
+    client: Res<RenetClient>,
+    mut target: ResMut<TargetServer>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    state: Res<State<GameState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !client.is_disconnected() {
+        return;
+    }
+
+    if disconnect_reason.0.is_none() {
+        disconnect_reason.0 = Some(match client.disconnect_reason() {
+            Some(reason) => format!("Disconnected: {}", reason),
+            None => "Lost connection to the server".into(),
+        });
+    }
+    info!("Disconnected from the server: {:?}", disconnect_reason.0);
+
+    // Leaving the game cleans up the connection, but the loading screen has nothing to clean up
+    if *state.get() == GameState::PreGameLoading {
+        reset_target(&mut target);
+    }
+    game_state.set(GameState::Menu);
 }
@@ -2,7 +2,6 @@ pub mod api;
 mod chat;
 mod cleanup;
 mod inputs;
-pub mod player;
 pub mod save;
 mod setup;
 mod world;
@@ -10,6 +9,5 @@ mod world;
 pub use chat::*;
 pub use cleanup::*;
 pub use inputs::*;
-pub use player::*;
 pub use setup::*;
 pub use world::request_world_update;
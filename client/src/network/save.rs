@@ -3,7 +3,7 @@ use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 
 // Send save request to server
-pub fn send_save_request_to_server(client: &mut ResMut<RenetClient>) {
-    send_network_action(client, NetworkAction::SaveWorldRequest);
+pub fn send_save_request_to_server(client: &mut ResMut<RenetClient>, session_token: u128) {
+    send_network_action(client, NetworkAction::SaveWorldRequest { session_token });
     debug!("Save request sent to server.");
 }
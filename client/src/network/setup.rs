@@ -1,29 +1,38 @@
 use bevy::prelude::*;
 use bevy_renet::{renet::RenetClient, RenetClientPlugin};
 use rand::Rng;
+use shared::auth::{connect_token_path, generate_connect_token, ConnectTokenFile};
 use shared::{get_shared_renet_config, GameServerConfig};
 
 use crate::menu::solo::SelectedWorld;
 use crate::network::world::update_world_from_network;
-use crate::network::{update_cached_chat_state, CachedChatConversation};
-use crate::player::{CurrentPlayerMarker, Player};
+use crate::network::{
+    disconnect_with_reason, update_cached_chat_state, CachedChatConversation, DisconnectReason,
+};
+use crate::player::{CurrentPlayerMarker, Player, PlayerPrediction};
+use crate::ui::FloatingStack;
 use crate::world::time::ClientTime;
 use crate::world::{RenderDistance, WorldRenderRequestUpdateEvent};
 use bevy_renet::renet::transport::{
-    ClientAuthentication, NetcodeClientTransport, NetcodeTransportError,
+    generate_random_bytes, ClientAuthentication, NetcodeClientTransport, NetcodeTransportError,
 };
 use bevy_renet::renet::DefaultChannel;
 use bevy_renet::transport::NetcodeClientPlugin;
-use bincode::Options;
 use shared::messages::{
-    AuthRegisterRequest, ChatConversation, ClientToServerMessage, PlayerId, PlayerSpawnEvent,
+    decode_message, encode_message, AuthRegisterRequest, ClientToServerMessage, EntityMessage,
+    MessageDecodeError, PlayerId, PlayerSpawnEvent, ServerToClientMessage,
 };
+use shared::players::Inventory;
+use shared::world::set_registry;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::{net::UdpSocket, thread, time::SystemTime};
 
 use crate::world::ClientWorldMap;
 use shared::GameFolderPaths;
 
+/// Validity of the connect token used to join a local server
+const LOCAL_CONNECT_TOKEN_EXPIRE_SECONDS: u64 = 60;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TargetServerState {
     Initial,
@@ -55,6 +64,8 @@ pub struct TargetServer {
     pub username: Option<String>,
     pub session_token: Option<u128>,
     pub state: TargetServerState,
+    /// Token used to join the server, if it requires one
+    pub connect_token: Option<ConnectTokenFile>,
 }
 
 pub fn add_base_netcode(app: &mut App) {
@@ -62,6 +73,7 @@ pub fn add_base_netcode(app: &mut App) {
 
     let client = RenetClient::new(get_shared_renet_config());
     app.insert_resource(client);
+    app.insert_resource(DisconnectReason::default());
 
     // Setup the transport layer
     app.add_plugins(NetcodeClientPlugin);
@@ -72,6 +84,7 @@ pub fn add_base_netcode(app: &mut App) {
         username: None,
         session_token: None,
         state: TargetServerState::Initial,
+        connect_token: None,
     });
 }
 
@@ -79,6 +92,7 @@ pub fn launch_local_server_system(
     mut target: ResMut<TargetServer>,
     selected_world: Res<SelectedWorld>,
     paths: Res<GameFolderPaths>,
+    current_profile: Res<CurrentPlayerProfile>,
 ) {
     if target.address.is_some() {
         debug!("Skipping launch local server");
@@ -93,6 +107,22 @@ pub fn launch_local_server_system(
         let addr = socket.local_addr().unwrap();
         debug!("Obtained UDP socket: {}", addr);
 
+        // The local server only lives for this game, so does its key
+        let private_key = generate_random_bytes();
+        let connect_token = match generate_connect_token(
+            &private_key,
+            &current_profile.name,
+            current_profile.id,
+            vec![addr],
+            LOCAL_CONNECT_TOKEN_EXPIRE_SECONDS,
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                error!("Failed to generate connect token: {}", e);
+                return;
+            }
+        };
+
         let world_name_clone = world_name.clone();
         let game_folder_path = paths.clone().game_folder_path;
         //
@@ -102,12 +132,15 @@ pub fn launch_local_server_system(
                 GameServerConfig {
                     world_name: world_name_clone,
                     is_solo: true,
+                    private_key: Some(private_key),
+                    public_addresses: vec![],
                 },
                 game_folder_path,
             );
         });
 
         target.address = Some(addr);
+        target.connect_token = Some(connect_token);
     } else {
         error!("Error: No world selected. Unable to launch the server.");
     }
@@ -116,71 +149,145 @@ pub fn launch_local_server_system(
 fn poll_reliable_ordered_messages(
     client: &mut ResMut<RenetClient>,
     chat_state: &mut ResMut<CachedChatConversation>,
-) {
+    disconnect_reason: &mut DisconnectReason,
+    ev_entities: &mut EventWriter<EntityMessage>,
+) -> Result<Vec<ServerToClientMessage>, MessageDecodeError> {
+    // World messages are handled along with the ones of the other channel, in order
+    let mut world_messages = Vec::new();
     while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
-        let message = bincode::options().deserialize::<ChatConversation>(&message);
-        match message {
-            Ok(data) => {
-                update_cached_chat_state(chat_state, data);
+        match decode_message(&message)? {
+            ServerToClientMessage::Chat(update) => {
+                update_cached_chat_state(chat_state, update);
             }
-            Err(e) => error!("err {}", e),
-        };
+            ServerToClientMessage::Disconnect { reason } => {
+                disconnect_with_reason(client, disconnect_reason, reason);
+            }
+            ServerToClientMessage::Entities(message) => {
+                ev_entities.send(message);
+            }
+            message => world_messages.push(message),
+        }
     }
+    Ok(world_messages)
 }
 
 fn poll_reliable_unordered_messages(
     client: &mut ResMut<RenetClient>,
+    world_messages: Vec<ServerToClientMessage>,
     world: &mut ResMut<ClientWorldMap>,
     client_time: ResMut<ClientTime>,
     ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
     players: &mut Query<(&mut Transform, &Player), With<Player>>,
     current_player_entity: Query<Entity, With<CurrentPlayerMarker>>,
-    render_distance: Res<RenderDistance>,
+    commands: &mut Commands,
     ev_spawn: &mut EventWriter<PlayerSpawnEvent>,
-) {
+    prediction: &mut ResMut<PlayerPrediction>,
+    inventory: &mut ResMut<Inventory>,
+    floating_stack: &mut Query<&mut FloatingStack>,
+) -> Result<(), MessageDecodeError> {
     update_world_from_network(
         client,
+        world_messages,
         world,
         client_time,
         ev_render,
         players,
         current_player_entity,
-        render_distance,
+        commands,
         ev_spawn,
-    );
+        prediction,
+        inventory,
+        floating_stack,
+    )
 }
 
 pub fn poll_network_messages(
     mut client: ResMut<RenetClient>,
-    mut chat_state: ResMut<CachedChatConversation>,
+    (mut chat_state, mut disconnect_reason, mut ev_entities): (
+        ResMut<CachedChatConversation>,
+        ResMut<DisconnectReason>,
+        EventWriter<EntityMessage>,
+    ),
     client_time: ResMut<ClientTime>,
     mut world: ResMut<ClientWorldMap>,
     mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
     mut players: Query<(&mut Transform, &Player), With<Player>>,
     current_player_entity: Query<Entity, With<CurrentPlayerMarker>>,
-    render_distance: Res<RenderDistance>,
+    mut commands: Commands,
     mut ev_spawn: EventWriter<PlayerSpawnEvent>,
+    mut prediction: ResMut<PlayerPrediction>,
+    mut inventory: ResMut<Inventory>,
+    mut floating_stack: Query<&mut FloatingStack>,
 ) {
-    poll_reliable_ordered_messages(&mut client, &mut chat_state);
-    poll_reliable_unordered_messages(
+    let result = poll_reliable_ordered_messages(
         &mut client,
-        &mut world,
-        client_time,
-        &mut ev_render,
-        &mut players,
-        current_player_entity,
-        render_distance,
-        &mut ev_spawn,
-    );
+        &mut chat_state,
+        &mut disconnect_reason,
+        &mut ev_entities,
+    )
+    .and_then(|world_messages| {
+        poll_reliable_unordered_messages(
+            &mut client,
+            world_messages,
+            &mut world,
+            client_time,
+            &mut ev_render,
+            &mut players,
+            current_player_entity,
+            &mut commands,
+            &mut ev_spawn,
+            &mut prediction,
+            &mut inventory,
+            &mut floating_stack,
+        )
+    });
+
+    // A packet we can't read means the server and the client don't understand each other anymore
+    if let Err(e) = result {
+        disconnect_with_reason(&mut client, &mut disconnect_reason, e.to_string());
+    }
 }
 
 pub fn init_server_connection(
     mut commands: Commands,
-    target: Res<TargetServer>,
+    mut target: ResMut<TargetServer>,
     current_player_id: Res<CurrentPlayerProfile>,
+    paths: Res<GameFolderPaths>,
 ) {
     let addr = target.address.unwrap();
     let id = current_player_id.into_inner().id;
+
+    // Secure servers hand out connect tokens, to be placed in the game folder
+    if target.connect_token.is_none() {
+        let path = connect_token_path(&paths.game_folder_path, &addr);
+        if path.exists() {
+            match ConnectTokenFile::load(&path) {
+                Ok(token) => target.connect_token = Some(token),
+                Err(e) => error!("Failed to load connect token {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    let connect_token = match target.connect_token.clone() {
+        Some(token_file) => {
+            target.username = Some(token_file.username.clone());
+            match token_file.connect_token() {
+                Ok(token) => Some(token),
+                Err(e) => {
+                    error!("Invalid connect token: {}", e);
+                    None
+                }
+            }
+        }
+        None => {
+            warn!(
+                "No connect token for {}, only servers without authentication will accept the connection",
+                addr
+            );
+            None
+        }
+    };
+
     commands.add(move |world: &mut World| {
         world.remove_resource::<RenetClient>();
         world.remove_resource::<NetcodeClientTransport>();
@@ -191,11 +298,14 @@ pub fn init_server_connection(
 
         info!("Attempting to connect to: {}", addr);
 
-        let authentication = ClientAuthentication::Unsecure {
-            server_addr: addr,
-            client_id: id,
-            user_data: None,
-            protocol_id: shared::PROTOCOL_ID,
+        let authentication = match connect_token {
+            Some(connect_token) => ClientAuthentication::Secure { connect_token },
+            None => ClientAuthentication::Unsecure {
+                server_addr: addr,
+                client_id: id,
+                user_data: None,
+                protocol_id: shared::NETCODE_PROTOCOL_ID,
+            },
         };
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let current_time = SystemTime::now()
@@ -222,6 +332,8 @@ pub fn establish_authenticated_connection_to_server(
     mut target: ResMut<TargetServer>,
     current_profile: Res<CurrentPlayerProfile>,
     mut ev_spawn: EventWriter<PlayerSpawnEvent>,
+    mut disconnect_reason: ResMut<DisconnectReason>,
+    render_distance: Res<RenderDistance>,
 ) {
     if target.session_token.is_some() {
         info!(
@@ -240,21 +352,35 @@ pub fn establish_authenticated_connection_to_server(
 
         let auth_msg = ClientToServerMessage::AuthRegisterRequest(AuthRegisterRequest {
             username: username.clone(),
+            render_distance: render_distance.distance,
+            vertical_render_distance: render_distance.vertical_distance,
         });
-        let auth_msg_encoded = bincode::options().serialize(&auth_msg).unwrap();
+        let auth_msg_encoded = encode_message(&auth_msg);
         client.send_message(DefaultChannel::ReliableOrdered, auth_msg_encoded);
         target.state = TargetServerState::Establising;
     }
 
     while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
-        let message =
-            bincode::options().deserialize::<shared::messages::AuthRegisterResponse>(&message);
-        if let Ok(message) = message {
-            target.username = Some(message.username);
-            target.session_token = Some(message.session_token);
-            target.state = TargetServerState::ConnectionEstablished;
-            ev_spawn.send(message.spawn_event);
-            info!("Connected! {:?}", target);
+        match decode_message(&message) {
+            Ok(ServerToClientMessage::AuthRegisterResponse(message)) => {
+                target.username = Some(message.username);
+                target.session_token = Some(message.session_token);
+                set_registry(*message.registry);
+                target.state = TargetServerState::ConnectionEstablished;
+                ev_spawn.send(message.spawn_event);
+                info!("Connected! {:?}", target);
+                // The chat backlog follows, and is read once connected
+                break;
+            }
+            Ok(ServerToClientMessage::Disconnect { reason }) => {
+                disconnect_with_reason(&mut client, &mut disconnect_reason, reason);
+                break;
+            }
+            Ok(message) => debug!("Ignored message before authentication: {:?}", message),
+            Err(e) => {
+                disconnect_with_reason(&mut client, &mut disconnect_reason, e.to_string());
+                break;
+            }
         }
     }
 }
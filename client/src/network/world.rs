@@ -1,13 +1,13 @@
 use crate::{
-    player::{CurrentPlayerMarker, Player},
+    player::{CurrentPlayerMarker, Player, PlayerPrediction},
+    ui::FloatingStack,
     world::ClientChunk,
 };
 use bevy::prelude::*;
 use bevy_renet::renet::{DefaultChannel, RenetClient};
-use bincode::Options;
 use shared::{
-    messages::{PlayerSpawnEvent, ServerToClientMessage},
-    world::{block_to_chunk_coord, chunk_in_radius},
+    messages::{decode_message, MessageDecodeError, PlayerSpawnEvent, ServerToClientMessage},
+    players::Inventory,
 };
 
 use crate::world::ClientWorldMap;
@@ -19,29 +19,27 @@ use super::api::send_network_action;
 
 pub fn update_world_from_network(
     client: &mut ResMut<RenetClient>,
+    ordered_messages: Vec<ServerToClientMessage>,
     world: &mut ResMut<ClientWorldMap>,
     mut client_time: ResMut<ClientTime>,
     ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
     players: &mut Query<(&mut Transform, &Player), With<Player>>,
     current_player_entity: Query<Entity, With<CurrentPlayerMarker>>,
-    render_distance: Res<RenderDistance>,
+    commands: &mut Commands,
     ev_spawn: &mut EventWriter<PlayerSpawnEvent>,
-) {
-    let (player_pos, current_player) = players.get(current_player_entity.single()).unwrap();
+    prediction: &mut ResMut<PlayerPrediction>,
+    inventory: &mut ResMut<Inventory>,
+    floating_stack: &mut Query<&mut FloatingStack>,
+) -> Result<(), MessageDecodeError> {
+    let (_, current_player) = players.get(current_player_entity.single()).unwrap();
     let current_player_id = current_player.id;
 
-    let player_pos = IVec3::new(
-        block_to_chunk_coord(player_pos.translation.x as i32),
-        0,
-        block_to_chunk_coord(player_pos.translation.z as i32),
-    );
-    let r = render_distance.distance as i32;
-
+    let mut messages = ordered_messages;
     while let Some(bytes) = client.receive_message(DefaultChannel::ReliableUnordered) {
-        let msg = bincode::options()
-            .deserialize::<ServerToClientMessage>(&bytes)
-            .unwrap();
+        messages.push(decode_message(&bytes)?);
+    }
 
+    for msg in messages {
         match msg {
             ServerToClientMessage::WorldUpdate(world_update) => {
                 debug!(
@@ -52,8 +50,8 @@ pub fn update_world_from_network(
                 trace!("Chunks positions : {:?}", world_update.new_map.keys());
 
                 for (pos, chunk) in world_update.new_map {
-                    // If the chunk is not in render distance range or is empty, do not consider it
-                    if !chunk_in_radius(&player_pos, &pos, r) || chunk.map.is_empty() {
+                    // The server only sends the chunks around the player, empty ones excepted
+                    if chunk.map.is_empty() {
                         continue;
                     }
 
@@ -94,23 +92,70 @@ pub fn update_world_from_network(
                 info!("Received SINGLE spawn event {:?}", spawn_event);
                 ev_spawn.send(spawn_event);
             }
+            ServerToClientMessage::BlockCorrection(correction) => {
+                debug!("Received block correction {:?}", correction);
+                match correction.block {
+                    Some(block) => world.set_block(&correction.position, block),
+                    None => {
+                        world.remove_block_by_coordinates(&correction.position);
+                    }
+                }
+                ev_render.send(WorldRenderRequestUpdateEvent::BlockToReload(
+                    correction.position,
+                ));
+            }
+            ServerToClientMessage::BlockChanges(block_changes) => {
+                debug!("Received {} block changes", block_changes.changes.len());
+                for (position, block) in block_changes.changes {
+                    match block {
+                        Some(block) => world.set_block(&position, block),
+                        None => {
+                            world.remove_block_by_coordinates(&position);
+                        }
+                    }
+                    ev_render.send(WorldRenderRequestUpdateEvent::BlockToReload(position));
+                }
+            }
+            ServerToClientMessage::UnloadChunks(chunks) => {
+                debug!("Unloading {} chunks", chunks.len());
+                for pos in chunks {
+                    if let Some(ClientChunk {
+                        entity: Some(entity),
+                        ..
+                    }) = world.map.remove(&pos)
+                    {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            }
+            ServerToClientMessage::PlayerStateUpdate(update) => {
+                trace!("Received player state update {:?}", update);
+                prediction.receive_server_update(update);
+            }
+            ServerToClientMessage::PlayerInventory(new_inventory) => {
+                debug!("Received inventory {:?}", new_inventory);
+                // The server put back the held stack, if any
+                for mut stack in floating_stack.iter_mut() {
+                    stack.items = None;
+                }
+                **inventory = new_inventory;
+            }
+            ServerToClientMessage::InventoryUpdate(update) => {
+                debug!("Received inventory update {:?}", update);
+                inventory.apply_diff(&update.slots);
+            }
             _ => {}
         }
     }
+    Ok(())
 }
 
-pub fn request_world_update(
-    client: &mut ResMut<RenetClient>,
-    requested_chunks: Vec<IVec3>,
-    render_distance: &RenderDistance,
-    player_chunk_pos: IVec3,
-) {
+pub fn request_world_update(client: &mut ResMut<RenetClient>, render_distance: &RenderDistance) {
     send_network_action(
         client,
         super::api::NetworkAction::WorldUpdateRequest {
-            requested_chunks,
-            player_chunk_pos,
             render_distance: render_distance.distance,
+            vertical_render_distance: render_distance.vertical_distance,
         },
     );
 }
@@ -0,0 +1,82 @@
+use crate::world::meshing::textured_cuboid;
+use crate::world::MaterialResource;
+use crate::GameState;
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+
+/// Slightly larger than a block, so that the cracks are drawn over its faces
+const OVERLAY_SIZE: f32 = 1.005;
+
+/// Block the player is breaking, by holding the mouse button down
+#[derive(Resource, Default, Debug)]
+pub struct BlockBreaking {
+    pub position: Option<IVec3>,
+    /// Time spent breaking the block, in seconds
+    pub elapsed: f32,
+    /// Time needed to break the block with the item held, in seconds
+    pub duration: f32,
+}
+
+/// Cracks drawn over the block being broken\
+/// `stages` are the meshes of the cracks, from the first to the last stage
+#[derive(Component)]
+pub struct BreakingOverlay {
+    stages: Vec<Handle<Mesh>>,
+}
+
+pub fn setup_breaking_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material_resource: Res<MaterialResource>,
+) {
+    // The textures of the stages are named DestroyStage0, DestroyStage1...
+    let stages: Vec<Handle<Mesh>> = (0..)
+        .map_while(|stage| {
+            material_resource
+                .blocks
+                .uvs
+                .get(&format!("DestroyStage{}", stage))
+        })
+        .map(|uvs| meshes.add(textured_cuboid(Vec3::splat(OVERLAY_SIZE), uvs)))
+        .collect();
+    if stages.is_empty() {
+        warn!("No texture found for the cracks of the blocks being broken");
+        return;
+    }
+
+    commands.spawn((
+        StateScoped(GameState::Game),
+        NotShadowCaster,
+        PbrBundle {
+            mesh: stages[0].clone(),
+            material: material_resource.blocks.material.clone().unwrap(),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        BreakingOverlay { stages },
+    ));
+}
+
+pub fn update_breaking_overlay(
+    breaking: Res<BlockBreaking>,
+    mut overlay_query: Query<(
+        &BreakingOverlay,
+        &mut Transform,
+        &mut Handle<Mesh>,
+        &mut Visibility,
+    )>,
+) {
+    let Ok((overlay, mut transform, mut mesh, mut visibility)) = overlay_query.get_single_mut()
+    else {
+        return;
+    };
+
+    let Some(position) = breaking.position.filter(|_| breaking.duration > 0.) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let stage = (breaking.elapsed / breaking.duration * overlay.stages.len() as f32) as usize;
+    *mesh = overlay.stages[stage.min(overlay.stages.len() - 1)].clone();
+    transform.translation = position.as_vec3() + Vec3::splat(0.5);
+    *visibility = Visibility::Visible;
+}
@@ -1,33 +1,66 @@
 use crate::camera::*;
-use crate::constants::{CUBE_SIZE, INTERACTION_DISTANCE};
+use crate::constants::CUBE_SIZE;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
 use crate::network::api::send_network_action;
 use crate::network::api::NetworkAction;
-use crate::player::inventory::*;
-use crate::player::spawn::Player;
 use crate::ui::hotbar::Hotbar;
-use crate::ui::UIMode;
+use crate::ui::{InventoryRoot, OpenedCraftingTable, UIMode};
 use crate::world::ClientWorldMap;
 use crate::world::WorldRenderRequestUpdateEvent;
+use crate::KeyMap;
 use bevy::math::NormedVectorSpace;
 use bevy::prelude::*;
 use bevy_mod_raycast::prelude::*;
 use bevy_renet::renet::RenetClient;
-use shared::world::{BlockData, ItemStack, ItemType};
+use shared::players::{
+    block_break_time, block_overlaps_player, is_block_breakable, Inventory, INTERACTION_DISTANCE,
+};
+use shared::world::{BlockData, BlockId, ItemType};
 
-use super::CurrentPlayerMarker;
+use super::{BlockBreaking, CurrentPlayerMarker};
 
 // Helper function to snap a Vec3 position to the grid
 fn snap_to_grid(position: Vec3) -> Vec3 {
     Vec3::new(position.x.round(), position.y.round(), position.z.round())
 }
 
+/// Tells the server the player stopped breaking a block, if they were
+fn abort_breaking(breaking: &mut BlockBreaking, client: &mut ResMut<RenetClient>) {
+    if let Some(position) = breaking.position {
+        send_network_action(client, NetworkAction::AbortBreaking { position });
+    }
+    *breaking = BlockBreaking::default();
+}
+
+/// Throws one item of the selected hotbar slot.\
+/// Predicted locally, the server sends the slot back if the drop is rejected
+pub fn drop_selected_item(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+    hotbar: Query<&Hotbar>,
+    mut inventory: ResMut<Inventory>,
+    mut client: ResMut<RenetClient>,
+) {
+    if *ui_mode == UIMode::Opened
+        || !is_action_just_pressed(GameAction::DropItem, &keyboard_input, &key_map)
+    {
+        return;
+    }
+    let slot = hotbar.single().selected;
+    if inventory.remove_item_from_stack(slot, 1) > 0 {
+        send_network_action(&mut client, NetworkAction::DropItem { slot });
+    }
+}
+
 // Function to handle block placement and breaking
 pub fn handle_block_interactions(
     queries: (
-        Query<&Player, With<CurrentPlayerMarker>>,
         Query<&mut Transform, With<CurrentPlayerMarker>>,
         Query<&RaycastSource<BlockRaycastSet>>,
         Query<&Hotbar>,
+        Query<&mut Visibility, With<InventoryRoot>>,
     ),
     resources: (
         ResMut<ClientWorldMap>,
@@ -35,22 +68,34 @@ pub fn handle_block_interactions(
         Res<UIMode>,
         ResMut<Inventory>,
         ResMut<RenetClient>,
+        ResMut<OpenedCraftingTable>,
+        ResMut<BlockBreaking>,
+        Res<Time>,
     ),
     mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
 ) {
-    let (player_query, mut p_transform, raycast_source, hotbar) = queries;
-    let (mut world_map, mouse_input, ui_mode, mut inventory, mut client) = resources;
-
-    let player = player_query.single().clone();
+    let (mut p_transform, raycast_source, hotbar, mut inventory_root) = queries;
+    let (
+        mut world_map,
+        mouse_input,
+        ui_mode,
+        mut inventory,
+        mut client,
+        mut opened_table,
+        mut breaking,
+        time,
+    ) = resources;
 
     if *ui_mode == UIMode::Opened {
+        abort_breaking(&mut breaking, &mut client);
         return;
     }
 
     let raycast_source = raycast_source.single();
 
-    // Handle left-click for breaking blocks
-    if mouse_input.just_pressed(MouseButton::Left) {
+    // Handle left-click for breaking blocks, which takes some time
+    let mut target = None;
+    if mouse_input.pressed(MouseButton::Left) {
         // Check if there are any intersections with a block
         if let Some((_, intersection)) = raycast_source.intersections().first() {
             // Check if block is close enough to the player
@@ -64,34 +109,45 @@ pub fn handle_block_interactions(
                     block_pos.z.floor() as i32,
                 );
 
-                // Remove the hit block
-                let block = world_map.remove_block_by_coordinates(&global_block_coords);
-
-                if let Some(block) = block {
-                    // add the block to the player's inventory
+                // Blocks that cannot be broken are not targeted
+                target = world_map
+                    .get_block_by_coordinates(&global_block_coords)
+                    .filter(|block| is_block_breakable(block))
+                    .map(|block| (global_block_coords, *block));
+            }
+        }
+    }
 
-                    // If block has corresponding item, add it to inventory
-                    for (item_id, nb) in block.id.get_drops(1) {
-                        inventory.add_item_to_inventory(ItemStack {
-                            item_id,
-                            item_type: item_id.get_default_type(),
-                            nb,
-                        });
-                    }
+    match target {
+        None => abort_breaking(&mut breaking, &mut client),
+        Some((position, block)) => {
+            if breaking.position == Some(position) {
+                breaking.elapsed += time.delta_seconds();
+            } else {
+                // Another block is aimed at, breaking starts over
+                abort_breaking(&mut breaking, &mut client);
+                breaking.position = Some(position);
+                send_network_action(&mut client, NetworkAction::StartBreaking { position });
+            }
 
-                    ev_render.send(WorldRenderRequestUpdateEvent::BlockToReload(
-                        global_block_coords,
-                    ));
-
-                    // Send the bloc to the serveur to delete it
-                    send_network_action(
-                        &mut client,
-                        NetworkAction::BlockInteraction {
-                            position: global_block_coords,
-                            block_type: None, // None signify suppression
-                        },
-                    );
-                }
+            let slot = hotbar.single().selected;
+            breaking.duration = block_break_time(&block, inventory.inner.get(&slot));
+
+            // Drops are spawned in the world by the server
+            if breaking.elapsed >= breaking.duration {
+                world_map.remove_block_by_coordinates(&position);
+                ev_render.send(WorldRenderRequestUpdateEvent::BlockToReload(position));
+
+                // Send the bloc to the serveur to delete it
+                send_network_action(
+                    &mut client,
+                    NetworkAction::BlockInteraction {
+                        position,
+                        block_type: None, // None signify suppression
+                        slot,
+                    },
+                );
+                *breaking = BlockBreaking::default();
             }
         }
     }
@@ -105,6 +161,19 @@ pub fn handle_block_interactions(
                 block_pos.y.floor() as i32,
                 block_pos.z.floor() as i32,
             );
+            let player_position = p_transform.single_mut().translation;
+
+            // Crafting tables are opened instead of having blocks placed against them
+            if world_map
+                .get_block_by_coordinates(&global_block_coords)
+                .is_some_and(|block| block.id == BlockId::CRAFTING_TABLE)
+            {
+                if (intersection.position() - player_position).norm() <= INTERACTION_DISTANCE {
+                    opened_table.0 = Some(global_block_coords);
+                    *inventory_root.single_mut() = Visibility::Visible;
+                }
+                return;
+            }
 
             // Get the normal of the face where the block will be placed
             let normal = intersection.normal(); // This is already a Vec3, no need to unwrap
@@ -113,24 +182,23 @@ pub fn handle_block_interactions(
             // Snap the position to the grid
             position = snap_to_grid(position);
 
-            // Difference vector between player position and block center
-            let distance = position + (Vec3::new(CUBE_SIZE, CUBE_SIZE, CUBE_SIZE) / 2.)
-                - p_transform.single_mut().translation;
+            let target_block_pos =
+                IVec3::new(position.x as i32, position.y as i32, position.z as i32);
 
             // Check if target space is close enough to the player
-            if (intersection.position() - p_transform.single_mut().translation).norm()
-                <= INTERACTION_DISTANCE
+            if (intersection.position() - player_position).norm() <= INTERACTION_DISTANCE
                 // Guarantees a block cannot be placed too close to the player (which would be unable to move because of constant collision)
-                && (distance.x.abs() > (CUBE_SIZE + player.width) / 2. || distance.z.abs() > (CUBE_SIZE + player.width ) / 2. || distance.y.abs() > (CUBE_SIZE + player.height) / 2.)
+                && !block_overlaps_player(&target_block_pos, &player_position)
             {
                 // Try to get item currently selected in player hotbar
-                if let Some(&item) = inventory.inner.get(&hotbar.single().selected) {
-                    inventory.remove_item_from_stack(hotbar.single().selected, 1);
-
+                let slot = hotbar.single().selected;
+                if let Some(&item) = inventory.inner.get(&slot) {
                     // Check if the item has a block counterpart
                     if let ItemType::Block(block_id) = item.item_type {
-                        let block_pos =
-                            IVec3::new(position.x as i32, position.y as i32, position.z as i32);
+                        // Predicted locally, the server sends the slot back if the placement is rejected
+                        inventory.remove_item_from_stack(slot, 1);
+
+                        let block_pos = target_block_pos;
                         let block =
                             BlockData::new(block_id, false, shared::world::BlockDirection::Front);
 
@@ -144,6 +212,7 @@ pub fn handle_block_interactions(
                             NetworkAction::BlockInteraction {
                                 position: block_pos,
                                 block_type: Some(block), // Some signify adding
+                                slot,
                             },
                         );
                     }
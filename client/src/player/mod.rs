@@ -1,8 +1,9 @@
+mod breaking;
 mod controller;
 mod interactions;
-pub(crate) mod inventory;
 mod spawn;
 
+pub use breaking::*;
 pub use controller::*;
 pub use interactions::*;
 pub use spawn::*;
@@ -4,16 +4,17 @@ use crate::{
 };
 use bevy::prelude::*;
 use shared::messages::{PlayerId, PlayerSpawnEvent};
+use shared::players::{PlayerMovementState, PLAYER_HEIGHT, PLAYER_WIDTH};
 
 #[derive(Component, Clone)]
 pub struct Player {
     pub id: PlayerId,
     pub name: String,
-    pub vertical_velocity: f32,
-    pub on_ground: bool,
+    pub movement: PlayerMovementState,
+    /// Direction the player was looking at when spawned
+    pub spawn_direction: Vec3,
     // pub view_mode: ViewMode,
     // pub is_chunk_debug_mode_enabled: bool,
-    pub is_flying: bool,
     // pub inventory: HashMap<RegistryId, items::Item>,
     pub height: f32,
     pub width: f32,
@@ -38,22 +39,16 @@ impl ViewMode {
 }
 
 impl Player {
-    pub fn new(id: PlayerId, name: String) -> Self {
+    pub fn new(id: PlayerId, name: String, position: Vec3, direction: Vec3) -> Self {
         Self {
             id,
             name,
-            vertical_velocity: 0.0,
-            on_ground: true,
-            is_flying: false,
-            height: 1.8,
-            width: 0.8,
+            movement: PlayerMovementState::new(position),
+            spawn_direction: direction,
+            height: PLAYER_HEIGHT,
+            width: PLAYER_WIDTH,
         }
     }
-
-    pub fn toggle_fly_mode(&mut self) {
-        self.is_flying = !self.is_flying;
-        self.vertical_velocity = 0.0; // Réinitialisation de la vélocité
-    }
 }
 
 pub fn spawn_player(
@@ -66,7 +61,6 @@ pub fn spawn_player(
     players: Query<&Player>,
 ) {
     let current_id = player_profile.into_inner().id;
-    let spawn_coords = Vec3::new(7.5, 80.0, 7.5);
     'event_loop: for event in ev_spawn.read() {
         info!("Executing spawn player for event: {:?}", event);
         for player in players.iter() {
@@ -79,7 +73,12 @@ pub fn spawn_player(
             }
         }
         let is_current_player = event.id == current_id;
-        let player = Player::new(event.id, event.name.clone());
+        let player = Player::new(
+            event.id,
+            event.name.clone(),
+            event.position,
+            event.direction,
+        );
 
         let color = if is_current_player {
             Color::srgba(1.0, 0.0, 0.0, 1.0)
@@ -98,7 +97,7 @@ pub fn spawn_player(
                     player.width,
                 ))),
                 material: materials.add(color),
-                transform: Transform::from_translation(spawn_coords),
+                transform: Transform::from_translation(event.position),
                 ..Default::default()
             },
             player,
@@ -118,7 +118,7 @@ pub fn setup_chat(
 
 pub fn render_chat(
     resources: (
-        Res<CachedChatConversation>,
+        ResMut<CachedChatConversation>,
         Res<AssetServer>,
         ResMut<RenetClient>,
         Res<ButtonInput<KeyCode>>,
@@ -139,12 +139,11 @@ pub fn render_chat(
             Without<ChatRoot>,
         >,
     ),
-    mut last_render_ts: Local<u64>,
     mut event: EventReader<TextInputSubmitEvent>,
     mut commands: Commands,
     paths: Res<GameFolderPaths>,
 ) {
-    let (cached_conv, asset_server, mut client, keyboard_input, key_map) = resources;
+    let (mut cached_conv, asset_server, mut client, keyboard_input, key_map) = resources;
     let (mut text_query, mut visibility_query, parent_query, mut animation_query) = queries;
 
     let (entity_check, mut inactive, mut value) = text_query.single_mut();
@@ -194,50 +193,52 @@ pub fn render_chat(
         }
     }
 
-    if let Some(conv) = &cached_conv.data {
-        for message in &conv.messages {
-            // If message too old, don't render
-            if message.date <= *last_render_ts {
-                continue;
-            }
+    let cached_conv = &mut *cached_conv;
+    for message in &cached_conv.messages {
+        // If message already rendered, skip it
+        if cached_conv
+            .last_rendered_id
+            .is_some_and(|last_id| message.id <= last_id)
+        {
+            continue;
+        }
 
-            *last_render_ts = message.date;
+        cached_conv.last_rendered_id = Some(message.id);
 
-            let msg = commands
-                .spawn((
-                    MessageAnimator {
-                        created_ts: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis() as u64,
-                    },
-                    TextBundle {
-                        text: Text::from_section(
-                            format!("<{}> : {}", message.author_name, message.content),
-                            TextStyle {
-                                font: asset_server
-                                    .load(format!("{}/fonts/gohu.ttf", paths.assets_folder_path)),
-                                font_size: 17.,
-                                color: Color::WHITE,
-                            }
-                            .clone(),
-                        ),
-                        visibility: Visibility::Visible,
-                        background_color: BackgroundColor(CHAT_COLOR),
-                        ..Default::default()
-                    },
-                ))
-                .id();
+        let msg = commands
+            .spawn((
+                MessageAnimator {
+                    created_ts: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis() as u64,
+                },
+                TextBundle {
+                    text: Text::from_section(
+                        format!("<{}> : {}", message.author_name, message.content),
+                        TextStyle {
+                            font: asset_server
+                                .load(format!("{}/fonts/gohu.ttf", paths.assets_folder_path)),
+                            font_size: 17.,
+                            color: Color::WHITE,
+                        }
+                        .clone(),
+                    ),
+                    visibility: Visibility::Visible,
+                    background_color: BackgroundColor(CHAT_COLOR),
+                    ..Default::default()
+                },
+            ))
+            .id();
 
-            commands.entity(parent).push_children(&[msg]);
-        }
+        commands.entity(parent).push_children(&[msg]);
+    }
 
-        // Prevents too much messages from building up on screen
-        if children.len() > CHAT_MAX_MESSAGES {
-            for i in children.len()..CHAT_MAX_MESSAGES {
-                commands.entity(parent).remove_children(&[children[i]]);
-                commands.entity(children[i]).despawn();
-            }
+    // Prevents too much messages from building up on screen
+    if children.len() > CHAT_MAX_MESSAGES {
+        for i in children.len()..CHAT_MAX_MESSAGES {
+            commands.entity(parent).remove_children(&[children[i]]);
+            commands.entity(children[i]).despawn();
         }
     }
 
@@ -0,0 +1,91 @@
+use super::{
+    update_inventory_cell, CraftingGrid, CraftingResultCell, CraftingTableCell, FloatingStack,
+    InventoryRoot, OpenedCraftingTable,
+};
+use crate::network::api::{send_network_action, NetworkAction};
+use crate::world::MaterialResource;
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::InventoryLayout;
+use shared::players::Inventory;
+use shared::world::{registry, CRAFTING_GRID_SIZE, INVENTORY_CRAFTING_GRID_SIZE};
+
+/// Shows the crafting grid matching the opened inventory and the result of its recipe\
+/// Clicking the result asks the server to craft it, nothing is predicted
+#[allow(clippy::too_many_arguments)]
+pub fn update_crafting_grid(
+    mut inventory: ResMut<Inventory>,
+    mut opened_table: ResMut<OpenedCraftingTable>,
+    mut client: ResMut<RenetClient>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    materials: Res<MaterialResource>,
+    (root_query, floating_stack_query): (
+        Query<&Visibility, With<InventoryRoot>>,
+        Query<&FloatingStack>,
+    ),
+    (mut grid_query, mut table_cells_query): (
+        Query<&mut Style, With<CraftingGrid>>,
+        Query<&mut Style, (With<CraftingTableCell>, Without<CraftingGrid>)>,
+    ),
+    mut result_query: Query<(&Interaction, &mut BorderColor, &Children), With<CraftingResultCell>>,
+    mut text_query: Query<&mut Text>,
+    mut atlas_query: Query<(&mut TextureAtlas, &mut Visibility), Without<InventoryRoot>>,
+    mut was_visible: Local<bool>,
+) {
+    if *root_query.single() != Visibility::Visible {
+        if *was_visible {
+            // The items left in the grid go back to the inventory when it is closed
+            let previous = inventory.inner.clone();
+            inventory.release_crafting_grid();
+            if previous != inventory.inner {
+                send_network_action(
+                    &mut client,
+                    NetworkAction::InventoryLayout(InventoryLayout {
+                        slots: inventory.inner.clone(),
+                        held: floating_stack_query.single().items,
+                    }),
+                );
+            }
+            opened_table.0 = None;
+            *was_visible = false;
+        }
+        return;
+    }
+    *was_visible = true;
+
+    let size = match opened_table.0 {
+        Some(_) => CRAFTING_GRID_SIZE,
+        None => INVENTORY_CRAFTING_GRID_SIZE,
+    };
+    grid_query.single_mut().grid_template_columns = RepeatedGridTrack::auto(size as u16);
+    for mut style in table_cells_query.iter_mut() {
+        style.display = match opened_table.0 {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+    }
+
+    let recipe = inventory
+        .crafting_grid(size)
+        .and_then(|grid| registry().find_recipe(&grid, size).cloned());
+    let result = recipe.map(|recipe| recipe.result_stack());
+
+    let (interaction, mut border_color, children) = result_query.single_mut();
+    let mut txt = text_query.get_mut(children[0]).unwrap();
+    let (mut atlas, mut visibility) = atlas_query.get_mut(children[1]).unwrap();
+    update_inventory_cell(&result, &mut txt, &mut visibility, &mut atlas, &materials);
+
+    if *interaction == Interaction::None {
+        border_color.0 = Color::srgb(0.3, 0.3, 0.3);
+        return;
+    }
+    border_color.0 = Color::WHITE;
+    if result.is_some() && mouse_input.just_pressed(MouseButton::Left) {
+        send_network_action(
+            &mut client,
+            NetworkAction::CraftItem {
+                table: opened_table.0,
+            },
+        );
+    }
+}
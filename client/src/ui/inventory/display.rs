@@ -2,7 +2,7 @@ use super::{add_item_floating_stack, remove_item_floating_stack};
 use crate::constants::MAX_HOTBAR_SLOTS;
 use crate::input::data::GameAction;
 use crate::input::keyboard::is_action_just_pressed;
-use crate::player::inventory::Inventory;
+use crate::network::api::{send_network_action, NetworkAction};
 use crate::ui::hotbar::Hotbar;
 use crate::ui::{FloatingStack, InventoryCell, InventoryRoot};
 use crate::world::MaterialResource;
@@ -19,7 +19,10 @@ use bevy::prelude::{
 use bevy::sprite::TextureAtlas;
 use bevy::ui::{BorderColor, Interaction};
 use bevy::window::PrimaryWindow;
-use shared::world::{ItemId, ItemStack};
+use bevy_renet::renet::RenetClient;
+use shared::messages::InventoryLayout;
+use shared::players::Inventory;
+use shared::world::{registry, ItemType};
 
 pub fn render_inventory_hotbar(
     (
@@ -39,12 +42,13 @@ pub fn render_inventory_hotbar(
         Query<&Window, With<PrimaryWindow>>,
         Query<&mut Hotbar>,
     ),
-    (keyboard_input, mouse_input, key_map, mut inventory, materials): (
+    (keyboard_input, mouse_input, key_map, mut inventory, materials, mut client): (
         Res<ButtonInput<KeyCode>>,
         Res<ButtonInput<MouseButton>>,
         Res<KeyMap>,
         ResMut<Inventory>,
         Res<MaterialResource>,
+        ResMut<RenetClient>,
     ),
     mut scroll: EventReader<MouseWheel>,
 ) {
@@ -57,24 +61,8 @@ pub fn render_inventory_hotbar(
     }
 
     if is_action_just_pressed(GameAction::DebugGetBlock, &keyboard_input, &key_map) {
-        debug!("Blocks given to user");
-        inventory.add_item_to_inventory(ItemStack {
-            item_id: ItemId::Glass,
-            item_type: ItemId::Glass.get_default_type(),
-            nb: 64,
-        });
-
-        inventory.add_item_to_inventory(ItemStack {
-            item_id: ItemId::Poppy,
-            item_type: ItemId::Poppy.get_default_type(),
-            nb: 64,
-        });
-
-        inventory.add_item_to_inventory(ItemStack {
-            item_id: ItemId::Dandelion,
-            item_type: ItemId::Dandelion.get_default_type(),
-            nb: 64,
-        });
+        debug!("Requesting debug blocks");
+        send_network_action(&mut client, NetworkAction::DebugGetBlocks);
     }
 
     let (mut style, mut floating_stack, children) = floating_stack_query.single_mut();
@@ -105,6 +93,11 @@ pub fn render_inventory_hotbar(
         &materials,
     );
 
+    // Keep the layout before clicks, to tell the server about any change
+    let previous_layout = (mouse_input.just_pressed(MouseButton::Left)
+        || mouse_input.just_pressed(MouseButton::Right))
+    .then(|| (inventory.inner.clone(), floating_stack.items));
+
     if let Some(c_pos) = window_query.single().cursor_position() {
         style.top = Val::Px(c_pos.y);
         style.left = Val::Px(c_pos.x);
@@ -239,6 +232,18 @@ pub fn render_inventory_hotbar(
             border_color.0 = Color::WHITE;
         }
     }
+
+    if let Some((slots, held)) = previous_layout {
+        if slots != inventory.inner || held != floating_stack.items {
+            send_network_action(
+                &mut client,
+                NetworkAction::InventoryLayout(InventoryLayout {
+                    slots: inventory.inner.clone(),
+                    held: floating_stack.items,
+                }),
+            );
+        }
+    }
 }
 
 pub fn update_inventory_cell(
@@ -250,11 +255,15 @@ pub fn update_inventory_cell(
 ) {
     // Set content
     if let Some(fstack) = stack {
-        txt.sections[0].value = format!("{:?}", fstack.nb);
+        // Tools don't stack, their remaining durability is shown instead
+        txt.sections[0].value = match fstack.item_type {
+            ItemType::Tool { durability } => format!("{:?}", durability),
+            _ => format!("{:?}", fstack.nb),
+        };
         atlas.index = (materials
             .items
             .uvs
-            .get(&format!("{:?}", fstack.item_id))
+            .get(&registry().item(fstack.item_id).texture)
             .unwrap()
             .u0
             * materials.items.uvs.len() as f32) as usize;
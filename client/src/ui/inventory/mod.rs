@@ -1,4 +1,4 @@
-use bevy::prelude::{Component, Query, ResMut, Resource, Visibility, With};
+use bevy::prelude::{Component, IVec3, Query, ResMut, Resource, Visibility, With};
 
 /// All UI dialogs toggling mouse visibility MUST use this in their bundle list\
 /// They must also possess the `visibility` attribute\
@@ -21,6 +21,22 @@ pub struct InventoryCell {
     pub id: u32,
 }
 
+/// Grid of the crafting cells, 3x3 with a crafting table and 2x2 otherwise
+#[derive(Component)]
+pub struct CraftingGrid;
+
+/// Crafting cells only shown with a crafting table
+#[derive(Component)]
+pub struct CraftingTableCell;
+
+/// Cell showing the result of the recipe of the crafting grid, crafted when clicked
+#[derive(Component)]
+pub struct CraftingResultCell;
+
+/// Crafting table the inventory was opened from, if any
+#[derive(Default, Resource)]
+pub struct OpenedCraftingTable(pub Option<IVec3>);
+
 /// The current selected stack, not considered in the player's inventory
 #[derive(Component)]
 pub struct FloatingStack {
@@ -43,10 +59,12 @@ pub fn set_ui_mode(mut ui_mode: ResMut<UIMode>, visibility: Query<&Visibility, W
     *ui_mode = UIMode::Closed;
 }
 
+mod crafting;
 mod display;
 pub mod items;
 mod setup;
 
+pub use crafting::*;
 pub use display::*;
 use items::*;
 pub use setup::*;
@@ -1,12 +1,75 @@
 use super::UiDialog;
 use crate::constants::{
-    HOTBAR_BORDER, HOTBAR_CELL_SIZE, HOTBAR_PADDING, MAX_HOTBAR_SLOTS, MAX_INVENTORY_SLOTS,
-    TEXTURE_SIZE,
+    HOTBAR_BORDER, HOTBAR_CELL_SIZE, HOTBAR_PADDING, MAX_HOTBAR_SLOTS, TEXTURE_SIZE,
+};
+use crate::ui::{
+    CraftingGrid, CraftingResultCell, CraftingTableCell, FloatingStack, InventoryCell,
+    InventoryDialog, InventoryRoot,
 };
-use crate::ui::{FloatingStack, InventoryCell, InventoryDialog, InventoryRoot};
 use crate::world::MaterialResource;
 use crate::GameState;
 use bevy::{prelude::*, ui::FocusPolicy};
+use shared::players::{crafting_grid_slot, MAX_INVENTORY_SLOTS};
+use shared::world::{CRAFTING_GRID_SIZE, INVENTORY_CRAFTING_GRID_SIZE};
+
+/// Spawns a cell showing a stack, its first child is the number of items and the second one their image
+fn spawn_cell(
+    builder: &mut ChildBuilder,
+    cell: impl Bundle,
+    img: &Handle<Image>,
+    atlas: &TextureAtlas,
+) {
+    builder
+        .spawn((
+            cell,
+            ButtonBundle {
+                border_color: BorderColor(Color::srgb(0.3, 0.3, 0.3)),
+                focus_policy: FocusPolicy::Block,
+                style: Style {
+                    width: Val::Px(HOTBAR_CELL_SIZE),
+                    height: Val::Px(HOTBAR_CELL_SIZE),
+                    margin: UiRect::ZERO,
+                    position_type: PositionType::Relative,
+                    padding: UiRect::all(Val::Px(HOTBAR_PADDING)),
+                    border: UiRect::all(Val::Px(HOTBAR_BORDER)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|btn| {
+            btn.spawn(TextBundle {
+                text: Text::from_section(
+                    "Test",
+                    TextStyle {
+                        font_size: 15.,
+                        ..Default::default()
+                    },
+                ),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            btn.spawn((
+                ImageBundle {
+                    z_index: ZIndex::Local(-1),
+                    style: Style {
+                        width: Val::Px(HOTBAR_CELL_SIZE - 2. * (HOTBAR_PADDING + HOTBAR_BORDER)),
+                        position_type: PositionType::Relative,
+                        ..Default::default()
+                    },
+                    image: UiImage {
+                        texture: img.clone_weak(),
+                        ..default()
+                    },
+                    ..Default::default()
+                },
+                atlas.clone(),
+            ));
+        });
+}
 
 pub fn setup_inventory(
     mut commands: Commands,
@@ -104,62 +167,81 @@ pub fn setup_inventory(
         })
         .with_children(|builder| {
             for i in MAX_HOTBAR_SLOTS..MAX_INVENTORY_SLOTS {
-                builder
-                    .spawn((
-                        InventoryCell { id: i },
-                        ButtonBundle {
-                            border_color: BorderColor(Color::srgb(0.3, 0.3, 0.3)),
-                            focus_policy: FocusPolicy::Block,
-                            style: Style {
-                                width: Val::Px(HOTBAR_CELL_SIZE),
-                                height: Val::Px(HOTBAR_CELL_SIZE),
-                                margin: UiRect::ZERO,
-                                position_type: PositionType::Relative,
-                                padding: UiRect::all(Val::Px(HOTBAR_PADDING)),
-                                border: UiRect::all(Val::Px(HOTBAR_BORDER)),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        },
-                    ))
-                    .with_children(|btn| {
-                        btn.spawn(TextBundle {
-                            text: Text::from_section(
-                                "Test",
-                                TextStyle {
-                                    font_size: 15.,
-                                    ..Default::default()
-                                },
-                            ),
-                            style: Style {
-                                position_type: PositionType::Absolute,
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        });
-                        btn.spawn((
-                            ImageBundle {
-                                z_index: ZIndex::Local(-1),
-                                style: Style {
-                                    width: Val::Px(
-                                        HOTBAR_CELL_SIZE - 2. * (HOTBAR_PADDING + HOTBAR_BORDER),
-                                    ),
-                                    position_type: PositionType::Relative,
-                                    ..Default::default()
-                                },
-                                image: UiImage {
-                                    texture: img.clone_weak(),
-                                    ..default()
-                                },
-                                ..Default::default()
-                            },
-                            atlas.clone(),
-                        ));
-                    });
+                spawn_cell(builder, InventoryCell { id: i }, &img, &atlas);
+            }
+        })
+        .id();
+
+    let crafting_grid = commands
+        .spawn((
+            CraftingGrid,
+            NodeBundle {
+                style: Style {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::auto(
+                        INVENTORY_CRAFTING_GRID_SIZE as u16,
+                    ),
+                    margin: UiRect::all(Val::Px(10.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|builder| {
+            for y in 0..CRAFTING_GRID_SIZE {
+                for x in 0..CRAFTING_GRID_SIZE {
+                    let cell = InventoryCell {
+                        id: crafting_grid_slot(x, y),
+                    };
+                    if x < INVENTORY_CRAFTING_GRID_SIZE && y < INVENTORY_CRAFTING_GRID_SIZE {
+                        spawn_cell(builder, cell, &img, &atlas);
+                    } else {
+                        spawn_cell(builder, (cell, CraftingTableCell), &img, &atlas);
+                    }
+                }
             }
         })
         .id();
 
+    let crafting_arrow = commands
+        .spawn(TextBundle::from_section(
+            "->",
+            TextStyle {
+                font_size: 24.,
+                ..Default::default()
+            },
+        ))
+        .id();
+
+    let crafting_result = commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|builder| {
+            spawn_cell(builder, CraftingResultCell, &img, &atlas);
+        })
+        .id();
+
+    let crafting = commands
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .id();
+
+    commands
+        .entity(crafting)
+        .push_children(&[crafting_grid, crafting_arrow, crafting_result]);
+
     let floating_stack = commands
         .spawn((
             FloatingStack { items: None },
@@ -206,7 +288,7 @@ pub fn setup_inventory(
 
     commands
         .entity(dialog)
-        .push_children(&[inventory_title, inventory_grid]);
+        .push_children(&[inventory_title, crafting, inventory_grid]);
 
     commands
         .entity(root)
@@ -1,4 +1,5 @@
 use crate::network::save::send_save_request_to_server;
+use crate::network::TargetServer;
 use bevy::{
     asset::AssetServer,
     color::{Alpha, Color},
@@ -126,6 +127,7 @@ pub fn render_pause_menu(
     mut game_state: ResMut<NextState<GameState>>,
     key_map: Res<KeyMap>,
     mut client: ResMut<RenetClient>,
+    target: Res<TargetServer>,
 ) {
     let (mut button, mut visibility) = queries;
     let mut vis = visibility.single_mut();
@@ -151,7 +153,10 @@ pub fn render_pause_menu(
                     *vis = Visibility::Hidden;
                 }
                 PauseButtonAction::Save => {
-                    send_save_request_to_server(&mut client);
+                    send_save_request_to_server(
+                        &mut client,
+                        target.session_token.unwrap_or_default(),
+                    );
                 }
             },
             Interaction::Hovered => {
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 use shared::world::BlockData;
+use shared::world::ChunkBlocks;
+use shared::world::WorldMap;
 use std::collections::HashSet;
 use std::hash::Hash;
 
@@ -16,11 +18,12 @@ use std::collections::HashMap;
 pub enum GlobalMaterial {
     Sun,
     Moon,
+    Liquids,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct ClientChunk {
-    pub map: HashMap<IVec3, BlockData>, // Maps block positions within a chunk to block IDs
+    pub map: ChunkBlocks, // Blocks of the chunk, indexed by their position within the chunk
     #[serde(skip)]
     pub entity: Option<Entity>,
 }
@@ -82,7 +85,13 @@ impl ClientWorldMap {
         let sub_y: i32 = ((y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
         let sub_z: i32 = ((z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
 
-        chunk.map.insert(IVec3::new(sub_x, sub_y, sub_z), block);
+        chunk.map.set(&IVec3::new(sub_x, sub_y, sub_z), block);
+    }
+}
+
+impl WorldMap for ClientWorldMap {
+    fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData> {
+        ClientWorldMap::get_block_by_coordinates(self, position)
     }
 }
 
@@ -190,6 +190,24 @@ pub fn create_all_atlases(
         &mut material_resource.blocks,
     );
 
+    // Liquids use the blocks atlas too, but are blended with what is behind them
+    if let Some(texture) = material_resource.blocks.texture.clone() {
+        material_resource
+            .global_materials
+            .entry(GlobalMaterial::Liquids)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color_texture: Some(texture),
+                    perceptual_roughness: BASE_ROUGHNESS,
+                    reflectance: BASE_SPECULAR_HIGHLIGHT,
+                    alpha_mode: AlphaMode::Blend,
+                    // the surface is seen from below too
+                    cull_mode: None,
+                    ..default()
+                })
+            });
+    }
+
     build_atlas(
         &mut atlases.1,
         &mut images,
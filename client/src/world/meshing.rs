@@ -5,9 +5,11 @@ use crate::world::{ClientChunk, ClientWorldMap};
 use bevy::{
     math::IVec3,
     prelude::*,
-    render::mesh::{Indices, PrimitiveTopology},
+    render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+};
+use shared::world::{
+    to_global_pos, BlockData, BlockDirection, BlockId, BlockTransparency, MAX_LIQUID_LEVEL,
 };
-use shared::world::{to_global_pos, BlockDirection, BlockId, BlockTransparency};
 
 use super::voxel::{Face, FaceDirection, VoxelShape};
 
@@ -25,34 +27,83 @@ impl UvCoords {
     }
 }
 
+/// Box textured with the same part of an atlas on every face
+pub fn textured_cuboid(size: Vec3, uvs: &UvCoords) -> Mesh {
+    let mut mesh = Mesh::from(Cuboid::from_size(size));
+    if let Some(VertexAttributeValues::Float32x2(coords)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0)
+    {
+        for uv in coords.iter_mut() {
+            uv[0] = uvs.u0 + uv[0] * (uvs.u1 - uvs.u0);
+            uv[1] = uvs.v0 + uv[1] * (uvs.v1 - uvs.v0);
+        }
+    }
+    mesh
+}
+
+/// Meshes of a chunk.\
+/// Liquids have their own, as they are drawn see-through and can't be aimed at
+#[derive(Debug)]
+pub(crate) struct ChunkMeshes {
+    pub solid: Mesh,
+    pub liquid: Option<Mesh>,
+}
+
+/// Vertex data of a mesh being built
+#[derive(Default)]
+struct MeshBuffers {
+    vertices: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices_offset: u32,
+}
+
+impl MeshBuffers {
+    fn into_mesh(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.vertices);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_indices(Indices::U32(self.indices));
+
+        if let Err(e) = mesh.generate_tangents() {
+            warn!("Error while generating tangents for the mesh : {:?}", e);
+        }
+        mesh
+    }
+}
+
 pub(crate) fn generate_chunk_mesh(
     world_map: &ClientWorldMap,
     chunk: &ClientChunk,
     chunk_pos: &IVec3,
     block_uvs: &HashMap<String, UvCoords>,
-) -> Mesh {
+) -> ChunkMeshes {
     let start = Instant::now();
 
-    let mut vertices: Vec<[f32; 3]> = Vec::new();
-    let mut indices: Vec<u32> = Vec::new();
-    let mut normals = Vec::new();
-    let mut uvs = Vec::new();
-    let mut colors = Vec::new();
-
-    let mut indices_offset = 0;
+    let mut solid = MeshBuffers::default();
+    let mut liquid = MeshBuffers::default();
 
     for (local_block_pos, block) in chunk.map.iter() {
         let x = local_block_pos.x as f32;
         let y = local_block_pos.y as f32;
         let z = local_block_pos.z as f32;
 
-        let global_block_pos = &to_global_pos(chunk_pos, local_block_pos);
+        let global_block_pos = &to_global_pos(chunk_pos, &local_block_pos);
         let visibility = block.id.get_visibility();
 
         if is_block_surrounded(world_map, global_block_pos, &visibility, &block.id) {
             continue;
         }
 
+        let buffers = if visibility == BlockTransparency::Liquid {
+            &mut liquid
+        } else {
+            &mut solid
+        };
+
         let mut local_vertices: Vec<[f32; 3]> = vec![];
         let mut local_indices: Vec<u32> = vec![];
         let mut local_normals: Vec<[f32; 3]> = vec![];
@@ -77,45 +128,54 @@ pub(crate) fn generate_chunk_mesh(
                     &mut local_normals,
                     &mut local_uvs,
                     &mut local_colors,
-                    &mut indices_offset,
+                    &mut buffers.indices_offset,
                     face,
                     uv_coords,
                 );
             }
         }
 
+        let height = block_height(world_map, global_block_pos, block);
         let local_vertices: Vec<[f32; 3]> = local_vertices
             .iter()
             .map(|v| {
                 let v = rotate_vertices(v, &block.direction);
                 [
                     v[0] + x,
-                    if block.flipped { 1. - v[1] } else { v[1] } + y,
+                    if block.flipped { 1. - v[1] } else { v[1] } * height + y,
                     v[2] + z,
                 ]
             })
             .collect();
 
-        vertices.extend(local_vertices);
-        indices.extend(local_indices);
-        normals.extend(local_normals);
-        uvs.extend(local_uvs);
-        colors.extend(local_colors);
+        buffers.vertices.extend(local_vertices);
+        buffers.indices.extend(local_indices);
+        buffers.normals.extend(local_normals);
+        buffers.uvs.extend(local_uvs);
+        buffers.colors.extend(local_colors);
     }
 
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, default());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices.to_vec());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
-    mesh.insert_indices(Indices::U32(indices));
+    let meshes = ChunkMeshes {
+        solid: solid.into_mesh(),
+        liquid: (!liquid.vertices.is_empty()).then(|| liquid.into_mesh()),
+    };
 
     trace!("Render time : {:?}", Instant::now() - start);
 
-    if let Err(e) = mesh.generate_tangents() {
-        warn!("Error while generating tangents for the mesh : {:?}", e);
+    meshes
+}
+
+/// Height of the block, lower than 1 for the liquids depending on their level.\
+/// A liquid with anything above it fills its whole block
+fn block_height(world_map: &ClientWorldMap, global_block_pos: &IVec3, block: &BlockData) -> f32 {
+    if !block.id.is_liquid()
+        || world_map
+            .get_block_by_coordinates(&(*global_block_pos + IVec3::Y))
+            .is_some()
+    {
+        return 1.;
     }
-    mesh
+    block.level as f32 / (MAX_LIQUID_LEVEL + 1) as f32
 }
 
 pub(crate) fn is_block_surrounded(
@@ -1,6 +1,6 @@
 pub mod data;
 pub mod materials;
-mod meshing;
+pub mod meshing;
 pub mod render;
 mod render_distance;
 pub mod time;
@@ -17,7 +17,10 @@ use shared::{
 
 use crate::{
     camera::BlockRaycastSet,
-    world::{self, MaterialResource, QueuedEvents, WorldRenderRequestUpdateEvent},
+    world::{
+        self, meshing::ChunkMeshes, GlobalMaterial, MaterialResource, QueuedEvents,
+        WorldRenderRequestUpdateEvent,
+    },
     GameState,
 };
 
@@ -25,7 +28,7 @@ use crate::world::ClientWorldMap;
 
 #[derive(Debug, Default, Resource)]
 pub struct QueuedMeshes {
-    pub meshes: Vec<Task<(IVec3, Mesh)>>,
+    pub meshes: Vec<Task<(IVec3, ChunkMeshes)>>,
 }
 
 fn update_chunk(
@@ -34,7 +37,7 @@ fn update_chunk(
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
     world_map: &mut ClientWorldMap,
-    new_mesh: Mesh,
+    new_meshes: ChunkMeshes,
 ) {
     let chunk = world_map.map.get_mut(chunk_pos).unwrap();
     let texture = material_resource.blocks.material.clone().unwrap();
@@ -55,7 +58,7 @@ fn update_chunk(
             .spawn((
                 StateScoped(GameState::Game),
                 PbrBundle {
-                    mesh: meshes.add(new_mesh),
+                    mesh: meshes.add(new_meshes.solid),
                     material: texture.clone(),
                     transform: chunk_t,
                     ..Default::default()
@@ -64,6 +67,22 @@ fn update_chunk(
             ))
             .id();
 
+        // Liquids are a child without raycast mesh, so that the blocks behind them can be aimed at
+        if let Some(liquid_mesh) = new_meshes.liquid {
+            let liquid_material = material_resource
+                .global_materials
+                .get(&GlobalMaterial::Liquids)
+                .unwrap()
+                .clone();
+            commands.entity(new_entity).with_children(|parent| {
+                parent.spawn(PbrBundle {
+                    mesh: meshes.add(liquid_mesh),
+                    material: liquid_material,
+                    ..Default::default()
+                });
+            });
+        }
+
         let ch = world_map.map.get_mut(chunk_pos).unwrap();
         ch.entity = Some(new_entity);
     }
@@ -142,7 +161,7 @@ pub fn world_render_system(
     // Iterate through queued meshes to see if they are completed
     queued_meshes.meshes.retain_mut(|task| {
         // If completed, then use the mesh to update the chunk and delete it from the meshing queue
-        if let Some((chunk_pos, new_mesh)) = block_on(future::poll_once(task)) {
+        if let Some((chunk_pos, new_meshes)) = block_on(future::poll_once(task)) {
             // Update the corresponding chunk
             if world_map.map.contains_key(&chunk_pos) {
                 update_chunk(
@@ -151,7 +170,7 @@ pub fn world_render_system(
                     &mut commands,
                     &mut meshes,
                     &mut world_map,
-                    new_mesh,
+                    new_meshes,
                 );
             }
             false
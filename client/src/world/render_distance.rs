@@ -1,29 +1,48 @@
 use crate::{
-    constants::DEFAULT_CHUNK_RENDER_DISTANCE_RADIUS,
+    constants::{
+        DEFAULT_CHUNK_RENDER_DISTANCE_RADIUS, DEFAULT_CHUNK_VERTICAL_RENDER_DISTANCE_RADIUS,
+    },
     input::{data::GameAction, keyboard::is_action_just_pressed},
+    network::request_world_update,
     KeyMap,
 };
 use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct RenderDistance {
     pub distance: u32,
+    /// Chunks loaded above and below the player
+    pub vertical_distance: u32,
+}
+
+impl Default for RenderDistance {
+    fn default() -> Self {
+        Self {
+            distance: DEFAULT_CHUNK_RENDER_DISTANCE_RADIUS,
+            vertical_distance: DEFAULT_CHUNK_VERTICAL_RENDER_DISTANCE_RADIUS,
+        }
+    }
 }
 
 pub fn render_distance_update_system(
     mut render_distance: ResMut<RenderDistance>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     key_map: Res<KeyMap>,
+    mut client: ResMut<RenetClient>,
 ) {
-    if render_distance.distance == 0 {
-        render_distance.distance = DEFAULT_CHUNK_RENDER_DISTANCE_RADIUS;
-    }
+    let previous_distance = render_distance.distance;
 
     if is_action_just_pressed(GameAction::RenderDistanceMinus, &keyboard_input, &key_map) {
-        render_distance.distance -= 1;
+        render_distance.distance = render_distance.distance.saturating_sub(1).max(1);
     }
 
     if is_action_just_pressed(GameAction::RenderDistancePlus, &keyboard_input, &key_map) {
         render_distance.distance += 1;
     }
+
+    // The server sends or unloads the chunks accordingly
+    if render_distance.distance != previous_distance {
+        request_world_update(&mut client, &render_distance);
+    }
 }
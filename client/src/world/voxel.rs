@@ -1,5 +1,4 @@
-use crate::constants::GRASS_COLOR;
-use shared::world::{BlockData, BlockId};
+use shared::world::{registry, BlockData, BlockShape};
 
 /// Specifies which position in the voxel this face occupies
 ///
@@ -38,62 +37,58 @@ pub struct VoxelShape {
 }
 
 impl VoxelShape {
-    /// Creates a VoxelShape based on the given BlockData
+    /// Creates a VoxelShape based on the given BlockData, from its definition in the registry
     pub fn create_from_block(block: &BlockData) -> VoxelShape {
-        match block.id {
-            BlockId::Grass => {
-                let mut shape = Self::full_cube(&block);
+        let registry = registry();
+        let definition = registry.block(block.id);
+        let textures = &definition.textures;
 
-                // Apply grass-specific textures and coloring
-                for (index, face) in shape.faces.iter_mut().enumerate() {
-                    if index == 0 {
-                        face.texture += "Top";
-                        for col in face.colors.iter_mut() {
-                            *col = GRASS_COLOR;
-                        }
+        match definition.shape {
+            BlockShape::Cross => {
+                let mut shape = Self::flora(&textures.all);
+                if let Some(tint) = definition.tint {
+                    for col in shape.faces[0].colors.iter_mut() {
+                        *col = tint;
                     }
                 }
-
                 shape
             }
-            BlockId::OakLog | BlockId::SpruceLog => {
-                let mut shape = Self::full_cube(&block);
-                shape.faces[0].texture += "Top";
-                shape.faces[1].texture += "Top";
-                shape
-            }
-            BlockId::OakLeaves | BlockId::SpruceLeaves => {
-                let mut shape = Self::full_cube(&block);
+            BlockShape::Cube => {
+                let mut shape = Self::full_cube(&textures.all);
 
-                // Apply leaves color
+                // Apply the textures and colors of each face
                 for face in shape.faces.iter_mut() {
-                    for col in face.colors.iter_mut() {
-                        *col = GRASS_COLOR;
+                    let (texture, tint) = match face.direction {
+                        FaceDirection::Top => {
+                            (&textures.top, definition.top_tint.or(definition.tint))
+                        }
+                        FaceDirection::Bottom => (&textures.bottom, definition.tint),
+                        FaceDirection::Front => (&textures.front, definition.tint),
+                        FaceDirection::Back => (&textures.back, definition.tint),
+                        FaceDirection::Left => (&textures.left, definition.tint),
+                        FaceDirection::Right => (&textures.right, definition.tint),
+                        FaceDirection::Inset => (&None, definition.tint),
+                    };
+                    if let Some(texture) = texture {
+                        face.texture = texture.clone();
+                    }
+                    if let Some(tint) = tint {
+                        for col in face.colors.iter_mut() {
+                            *col = tint;
+                        }
                     }
                 }
 
                 shape
             }
-            BlockId::Debug => {
-                let mut shape = Self::full_cube(&block);
-                shape.faces[0].texture = "Top".into();
-                shape.faces[1].texture = "Down".into();
-                shape.faces[2].texture = "Front".into();
-                shape.faces[3].texture = "Back".into();
-                shape.faces[4].texture = "Left".into();
-                shape.faces[5].texture = "Right".into();
-                shape
-            }
-            BlockId::Poppy | BlockId::Dandelion => Self::flora(block),
-            _ => Self::full_cube(block),
         }
     }
 
-    pub fn full_cube(block: &BlockData) -> Self {
+    pub fn full_cube(texture: &str) -> Self {
         VoxelShape {
             faces: vec![
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Top,
                     vertices: vec![[0., 1., 1.], [1., 1., 1.], [1., 1., 0.], [0., 1., 0.]],
                     indices: vec![0, 1, 2, 2, 3, 0],
@@ -112,7 +107,7 @@ impl VoxelShape {
                     uvs: vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]],
                 },
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Bottom,
                     vertices: vec![[0., 0., 0.], [1., 0., 0.], [1., 0., 1.], [0., 0., 1.]],
                     indices: vec![0, 1, 2, 2, 3, 0],
@@ -131,7 +126,7 @@ impl VoxelShape {
                     uvs: vec![[1., 0.], [0., 0.], [0., 1.], [1., 1.]],
                 },
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Front,
                     vertices: vec![[1., 1., 0.], [0., 1., 0.], [0., 0., 0.], [1., 0., 0.]],
                     indices: vec![0, 3, 2, 2, 1, 0],
@@ -150,7 +145,7 @@ impl VoxelShape {
                     uvs: vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]],
                 },
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Back,
                     vertices: vec![[1., 1., 1.], [0., 1., 1.], [0., 0., 1.], [1., 0., 1.]],
                     indices: vec![0, 1, 2, 2, 3, 0],
@@ -169,7 +164,7 @@ impl VoxelShape {
                     uvs: vec![[1., 0.], [0., 0.], [0., 1.], [1., 1.]],
                 },
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Left,
                     vertices: vec![[0., 1., 1.], [0., 1., 0.], [0., 0., 0.], [0., 0., 1.]],
                     indices: vec![3, 0, 1, 1, 2, 3],
@@ -188,7 +183,7 @@ impl VoxelShape {
                     uvs: vec![[1., 0.], [0., 0.], [0., 1.], [1., 1.]],
                 },
                 Face {
-                    texture: format!("{:?}", block.id),
+                    texture: texture.into(),
                     direction: FaceDirection::Right,
                     vertices: vec![[1., 1., 0.], [1., 1., 1.], [1., 0., 1.], [1., 0., 0.]],
                     indices: vec![0, 1, 2, 2, 3, 0],
@@ -210,7 +205,7 @@ impl VoxelShape {
         }
     }
 
-    pub fn flora(block: &BlockData) -> VoxelShape {
+    pub fn flora(texture: &str) -> VoxelShape {
         VoxelShape {
             faces: vec![Face {
                 direction: FaceDirection::Inset,
@@ -264,7 +259,7 @@ impl VoxelShape {
                     [1., 0.],
                     [1., 0.],
                 ],
-                texture: format!("{:?}", block.id),
+                texture: texture.into(),
             }],
         }
     }
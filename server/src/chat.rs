@@ -1,36 +1,88 @@
-use crate::dispatcher::BroadcastTimer;
+use crate::init::ServerLobby;
 use bevy::prelude::*;
-use bevy_renet::renet::{DefaultChannel, RenetServer};
-use bincode::Options;
-use shared::messages::ChatConversation;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
+use shared::messages::{
+    encode_message, ChatConversation, ChatMessage, ChatMessageId, ChatUpdate,
+    ServerToClientMessage, CHAT_HISTORY_LENGTH,
+};
 
-#[derive(Event)]
-pub struct ChatMessageEvent;
+/// Author of the messages sent by the server itself
+pub const SERVER_AUTHOR_NAME: &str = "Server";
+
+/// Latest messages of the conversation, and the id of the next message
+#[derive(Resource, Default, Debug)]
+pub struct ChatHistory {
+    pub conversation: ChatConversation,
+    next_id: ChatMessageId,
+}
+
+impl ChatHistory {
+    /// Stamps a new message with an id and the current date
+    fn new_message(&mut self, author_name: &str, content: String) -> ChatMessage {
+        let date = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let id = self.next_id;
+        self.next_id += 1;
+        ChatMessage {
+            id,
+            author_name: author_name.into(),
+            date,
+            content,
+        }
+    }
+
+    fn push(&mut self, message: ChatMessage) {
+        let messages = &mut self.conversation.messages;
+        messages.push_back(message);
+        while messages.len() > CHAT_HISTORY_LENGTH {
+            messages.pop_front();
+        }
+    }
+}
 
 pub fn setup_chat_resources(app: &mut App) {
-    app.insert_resource(ChatConversation { ..default() });
-    app.add_event::<ChatMessageEvent>();
+    app.insert_resource(ChatHistory::default());
 }
 
-pub fn broadcast_chat_messages(
-    mut server: ResMut<RenetServer>,
-    chat_messages: Res<ChatConversation>,
-    time: Res<Time>,
-    mut timer: ResMut<BroadcastTimer>,
-    mut ev_chat: EventReader<ChatMessageEvent>,
+fn send_chat_update(server: &mut RenetServer, client_id: ClientId, update: ChatUpdate) {
+    let payload = encode_message(&ServerToClientMessage::Chat(update));
+    server.send_message(client_id, DefaultChannel::ReliableOrdered, payload);
+}
+
+/// Sends the latest messages to a player who just joined
+pub fn send_chat_backlog(server: &mut RenetServer, client_id: ClientId, history: &ChatHistory) {
+    let backlog = ChatUpdate::Backlog(history.conversation.clone());
+    send_chat_update(server, client_id, backlog);
+}
+
+/// Adds a message to the conversation and sends it to every player
+pub fn broadcast_chat_message(
+    server: &mut RenetServer,
+    lobby: &ServerLobby,
+    history: &mut ChatHistory,
+    author_name: &str,
+    content: String,
 ) {
-    timer.timer.tick(time.delta());
-    if timer.timer.finished() || !ev_chat.is_empty() {
-        trace!(
-            "Broadcasting chat history, {} messages",
-            chat_messages.messages.len()
-        );
-        let cm: ChatConversation = chat_messages.into_inner().clone();
-        let serialized = bincode::options().serialize(&cm).unwrap();
-        trace!("world {:?}", cm);
-        trace!("serialized: {:?}", serialized);
-        server.broadcast_message(DefaultChannel::ReliableOrdered, serialized);
-        ev_chat.clear();
-        timer.timer.reset();
+    let message = history.new_message(author_name, content);
+    trace!("Broadcasting chat message {:?}", message);
+    history.push(message.clone());
+
+    // Players who have not logged in yet will get the message in their backlog
+    for player_id in lobby.players.keys() {
+        let update = ChatUpdate::Message(message.clone());
+        send_chat_update(server, ClientId::from_raw(*player_id), update);
     }
 }
+
+/// Sends a chat message to a single player
+pub fn send_private_message(
+    server: &mut RenetServer,
+    history: &mut ChatHistory,
+    client_id: ClientId,
+    content: String,
+) {
+    let message = history.new_message(SERVER_AUTHOR_NAME, content);
+    send_chat_update(server, client_id, ChatUpdate::Message(message));
+}
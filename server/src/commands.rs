@@ -0,0 +1,428 @@
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetServer};
+use shared::messages::PlayerId;
+use shared::world::{registry, ItemId, ItemStack, ServerWorldMap, WorldSeed};
+use shared::{GameFolderPaths, GameServerConfig, DAY_DURATION, MIDNIGHT, NOON};
+
+use crate::chat::{send_private_message, ChatHistory};
+use crate::disconnect::{disconnect_client, PendingDisconnections};
+use crate::entities::dropped_items::ItemDropEvent;
+use crate::init::{ServerLobby, ServerTime};
+use crate::inventory::{send_inventory_diff, PlayerInventories};
+use crate::permissions::{
+    get_permissions_path, save_permissions, PermissionLevel, ServerPermissions, LEVEL_ADMIN,
+    LEVEL_GAME_MASTER, LEVEL_MODERATOR, LEVEL_PLAYER,
+};
+use crate::player::{teleport_player, PlayerMovements};
+use crate::world::save::SaveRequestEvent;
+
+/// A chat line starting with `/`, sent by a player
+#[derive(Event, Debug)]
+pub struct CommandEvent {
+    pub player_id: PlayerId,
+    /// The line without its leading `/`
+    pub command: String,
+}
+
+/// Text sent back to the player who ran the command
+type CommandResult = Result<String, String>;
+
+/// Most items a single `/give` can hand out
+const MAX_GIVE_COUNT: u32 = 64 * 36;
+
+struct CommandInfo {
+    name: &'static str,
+    usage: &'static str,
+    level: PermissionLevel,
+}
+
+const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "kick",
+        usage: "/kick <player>",
+        level: LEVEL_MODERATOR,
+    },
+    CommandInfo {
+        name: "tp",
+        usage: "/tp [player] <x> <y> <z> | /tp <player> <target>",
+        level: LEVEL_MODERATOR,
+    },
+    CommandInfo {
+        name: "time",
+        usage: "/time set <day|night|seconds>",
+        level: LEVEL_GAME_MASTER,
+    },
+    CommandInfo {
+        name: "give",
+        usage: "/give <player> <item> [count]",
+        level: LEVEL_GAME_MASTER,
+    },
+    CommandInfo {
+        name: "seed",
+        usage: "/seed",
+        level: LEVEL_GAME_MASTER,
+    },
+    CommandInfo {
+        name: "save",
+        usage: "/save",
+        level: LEVEL_ADMIN,
+    },
+    CommandInfo {
+        name: "stop",
+        usage: "/stop",
+        level: LEVEL_ADMIN,
+    },
+    CommandInfo {
+        name: "ban",
+        usage: "/ban <player>",
+        level: LEVEL_ADMIN,
+    },
+    CommandInfo {
+        name: "op",
+        usage: "/op <player> [level]",
+        level: LEVEL_ADMIN,
+    },
+];
+
+/// Permission level of a connected player.\
+/// In solo games, the only player owns the world and can do anything
+pub fn player_level(
+    permissions: &ServerPermissions,
+    config: &GameServerConfig,
+    username: &str,
+) -> PermissionLevel {
+    if config.is_solo {
+        LEVEL_ADMIN
+    } else {
+        permissions.level_of(username)
+    }
+}
+
+fn find_player(lobby: &ServerLobby, username: &str) -> Result<PlayerId, String> {
+    lobby
+        .players
+        .iter()
+        .find(|(_, name)| *name == username)
+        .map(|(id, _)| *id)
+        .ok_or_else(|| format!("Player {} is not connected", username))
+}
+
+fn parse_position(args: &[&str]) -> Result<Vec3, String> {
+    let coords = args
+        .iter()
+        .map(|arg| arg.parse::<f32>().ok().filter(|coord| coord.is_finite()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("Invalid position: {}", args.join(" ")))?;
+    Ok(Vec3::new(coords[0], coords[1], coords[2]))
+}
+
+/// Accepts item names as `OakLog` or `oak_log`
+fn parse_item_id(name: &str) -> Option<ItemId> {
+    let variant: String = name
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    registry().item_by_name(&variant).map(|item| item.id)
+}
+
+/// Operators can only kick or ban players with a lower permission level than theirs
+fn check_outranks(
+    permissions: &ServerPermissions,
+    sender_level: PermissionLevel,
+    username: &str,
+) -> Result<(), String> {
+    if permissions.level_of(username) >= sender_level {
+        return Err(format!(
+            "{} has a permission level as high as yours",
+            username
+        ));
+    }
+    Ok(())
+}
+
+fn kick(
+    server: &mut RenetServer,
+    lobby: &ServerLobby,
+    pending: &mut PendingDisconnections,
+    permissions: &ServerPermissions,
+    sender_level: PermissionLevel,
+    args: &[&str],
+) -> CommandResult {
+    let [username] = args else {
+        return Err("Usage: /kick <player>".into());
+    };
+    check_outranks(permissions, sender_level, username)?;
+    let player_id = find_player(lobby, username)?;
+    disconnect_client(
+        server,
+        pending,
+        ClientId::from_raw(player_id),
+        "Kicked by an operator".into(),
+    );
+    Ok(format!("Kicked {}", username))
+}
+
+fn teleport(
+    server: &mut RenetServer,
+    lobby: &ServerLobby,
+    movements: &mut PlayerMovements,
+    world_map: &mut ServerWorldMap,
+    sender_id: PlayerId,
+    args: &[&str],
+) -> CommandResult {
+    let (player_id, position) = match args {
+        [x, y, z] => (sender_id, parse_position(&[x, y, z])?),
+        [username, x, y, z] => (find_player(lobby, username)?, parse_position(&[x, y, z])?),
+        [username, target] => {
+            let target_id = find_player(lobby, target)?;
+            let position = movements
+                .players
+                .get(&target_id)
+                .map(|movement| movement.state.position)
+                .ok_or_else(|| format!("Player {} has not spawned yet", target))?;
+            (find_player(lobby, username)?, position)
+        }
+        _ => return Err("Usage: /tp [player] <x> <y> <z> | /tp <player> <target>".into()),
+    };
+
+    let Some(movement) = movements.players.get_mut(&player_id) else {
+        return Err("This player has not spawned yet".into());
+    };
+    teleport_player(server, world_map, player_id, movement, position);
+    Ok(format!(
+        "Teleported {} to {:.1} {:.1} {:.1}",
+        lobby.players[&player_id], position.x, position.y, position.z
+    ))
+}
+
+fn set_time(time: &mut ServerTime, args: &[&str]) -> CommandResult {
+    let ["set", value] = args else {
+        return Err("Usage: /time set <day|night|seconds>".into());
+    };
+    // Day and night keep the current day count
+    let day_start = time.0 - time.0 % DAY_DURATION;
+    time.0 = match *value {
+        "day" => day_start + NOON,
+        "night" => day_start + MIDNIGHT,
+        value => value
+            .parse()
+            .map_err(|_| format!("Invalid time: {}", value))?,
+    };
+    Ok(format!("Time set to {}", time.0))
+}
+
+fn give(
+    server: &mut RenetServer,
+    lobby: &ServerLobby,
+    inventories: &mut PlayerInventories,
+    world_map: &ServerWorldMap,
+    ev_item_drop: &mut EventWriter<ItemDropEvent>,
+    args: &[&str],
+) -> CommandResult {
+    let (username, item, count) = match args {
+        [username, item] => (username, item, "1"),
+        [username, item, count] => (username, item, *count),
+        _ => return Err("Usage: /give <player> <item> [count]".into()),
+    };
+    let player_id = find_player(lobby, username)?;
+    let item_id = parse_item_id(item).ok_or_else(|| format!("Unknown item: {}", item))?;
+    let count: u32 = count
+        .parse()
+        .ok()
+        .filter(|count| (1..=MAX_GIVE_COUNT).contains(count))
+        .ok_or_else(|| format!("Count must be between 1 and {}", MAX_GIVE_COUNT))?;
+
+    let inventory = inventories.get_or_create(username);
+    let previous = inventory.clone();
+    let leftover = inventory.add_item_to_inventory(ItemStack {
+        item_id,
+        item_type: item_id.get_default_type(),
+        nb: count,
+    });
+    // What does not fit in the inventory falls at the feet of the player
+    if let (Some(stack), Some(position)) = (leftover, world_map.player_positions.get(&player_id)) {
+        ev_item_drop.send(ItemDropEvent::thrown(*position, Vec3::ZERO, stack));
+    }
+    send_inventory_diff(server, ClientId::from_raw(player_id), inventory, &previous);
+    Ok(format!("Gave {} {:?} to {}", count, item_id, username))
+}
+
+fn ban(
+    server: &mut RenetServer,
+    lobby: &ServerLobby,
+    pending: &mut PendingDisconnections,
+    permissions: &mut ServerPermissions,
+    paths: &GameFolderPaths,
+    sender_level: PermissionLevel,
+    args: &[&str],
+) -> CommandResult {
+    let [username] = args else {
+        return Err("Usage: /ban <player>".into());
+    };
+    check_outranks(permissions, sender_level, username)?;
+    permissions.banned.insert(username.to_string());
+    save_permissions(permissions, &get_permissions_path(&paths.game_folder_path))
+        .map_err(|e| format!("Failed to save bans: {}", e))?;
+
+    if let Ok(player_id) = find_player(lobby, username) {
+        disconnect_client(
+            server,
+            pending,
+            ClientId::from_raw(player_id),
+            "You are banned from this server".into(),
+        );
+    }
+    Ok(format!("Banned {}", username))
+}
+
+fn op(
+    permissions: &mut ServerPermissions,
+    paths: &GameFolderPaths,
+    sender_level: PermissionLevel,
+    args: &[&str],
+) -> CommandResult {
+    let (username, level) = match args {
+        [username] => (username, LEVEL_MODERATOR),
+        [username, level] => (
+            username,
+            level
+                .parse()
+                .ok()
+                .filter(|level| (LEVEL_PLAYER..=LEVEL_ADMIN).contains(level))
+                .ok_or_else(|| {
+                    format!("Level must be between {} and {}", LEVEL_PLAYER, LEVEL_ADMIN)
+                })?,
+        ),
+        _ => return Err("Usage: /op <player> [level]".into()),
+    };
+    // Nobody can grant more than they have
+    if level > sender_level {
+        return Err(format!("You cannot grant level {}", level));
+    }
+    permissions.set_level(username, level);
+    save_permissions(permissions, &get_permissions_path(&paths.game_folder_path))
+        .map_err(|e| format!("Failed to save operators: {}", e))?;
+    Ok(format!("{} now has permission level {}", username, level))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn handle_commands(
+    mut events: EventReader<CommandEvent>,
+    (mut server, lobby, mut movements, mut world_map, mut inventories, mut chat_history): (
+        ResMut<RenetServer>,
+        Res<ServerLobby>,
+        ResMut<PlayerMovements>,
+        ResMut<ServerWorldMap>,
+        ResMut<PlayerInventories>,
+        ResMut<ChatHistory>,
+    ),
+    (mut permissions, config, paths, mut time, seed): (
+        ResMut<ServerPermissions>,
+        Res<GameServerConfig>,
+        Res<GameFolderPaths>,
+        ResMut<ServerTime>,
+        Res<WorldSeed>,
+    ),
+    (mut ev_save, mut ev_app_exit, mut pending, mut ev_item_drop): (
+        EventWriter<SaveRequestEvent>,
+        EventWriter<AppExit>,
+        ResMut<PendingDisconnections>,
+        EventWriter<ItemDropEvent>,
+    ),
+) {
+    for event in events.read() {
+        let Some(username) = lobby.players.get(&event.player_id) else {
+            continue;
+        };
+        let client_id = ClientId::from_raw(event.player_id);
+        let mut words = event.command.split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        let args: Vec<&str> = words.collect();
+
+        let Some(info) = COMMANDS.iter().find(|info| info.name == name) else {
+            send_private_message(
+                &mut server,
+                &mut chat_history,
+                client_id,
+                format!("Unknown command: /{}", name),
+            );
+            continue;
+        };
+
+        let level = player_level(&permissions, &config, username);
+        if level < info.level {
+            info!("{} is not allowed to run /{}", username, name);
+            send_private_message(
+                &mut server,
+                &mut chat_history,
+                client_id,
+                format!("You are not allowed to use {}", info.usage),
+            );
+            continue;
+        }
+
+        info!("{} ran /{}", username, event.command);
+        let result = match name {
+            "kick" => kick(
+                &mut server,
+                &lobby,
+                &mut pending,
+                &permissions,
+                level,
+                &args,
+            ),
+            "tp" => teleport(
+                &mut server,
+                &lobby,
+                &mut movements,
+                &mut world_map,
+                event.player_id,
+                &args,
+            ),
+            "time" => set_time(&mut time, &args),
+            "give" => give(
+                &mut server,
+                &lobby,
+                &mut inventories,
+                &world_map,
+                &mut ev_item_drop,
+                &args,
+            ),
+            "seed" => Ok(format!("Seed: {}", seed.0)),
+            "save" => {
+                ev_save.send(SaveRequestEvent);
+                Ok("Saving the world".into())
+            }
+            "stop" => {
+                ev_save.send(SaveRequestEvent);
+                ev_app_exit.send(AppExit::Success);
+                Ok("Stopping the server".into())
+            }
+            "ban" => ban(
+                &mut server,
+                &lobby,
+                &mut pending,
+                &mut permissions,
+                &paths,
+                level,
+                &args,
+            ),
+            "op" => op(&mut permissions, &paths, level, &args),
+            _ => unreachable!(),
+        };
+
+        let reply = match result {
+            Ok(reply) => reply,
+            Err(error) => error,
+        };
+        send_private_message(&mut server, &mut chat_history, client_id, reply);
+    }
+}
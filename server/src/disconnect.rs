@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
+use shared::messages::{encode_message, ServerToClientMessage};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Time given to a client to receive the reason of its disconnection
+const DISCONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Clients told to leave, disconnected once their timer is over
+#[derive(Resource, Default)]
+pub struct PendingDisconnections {
+    timers: HashMap<ClientId, Timer>,
+}
+
+impl PendingDisconnections {
+    pub fn contains(&self, client_id: ClientId) -> bool {
+        self.timers.contains_key(&client_id)
+    }
+
+    pub fn remove(&mut self, client_id: ClientId) {
+        self.timers.remove(&client_id);
+    }
+}
+
+/// Tells a client why it has to leave, and disconnects it shortly after.\
+/// Disconnecting right away would drop the message before it is sent
+pub fn disconnect_client(
+    server: &mut RenetServer,
+    pending: &mut PendingDisconnections,
+    client_id: ClientId,
+    reason: String,
+) {
+    info!("Disconnecting client {}: {}", client_id, reason);
+    let payload = encode_message(&ServerToClientMessage::Disconnect { reason });
+    server.send_message(client_id, DefaultChannel::ReliableOrdered, payload);
+    pending
+        .timers
+        .entry(client_id)
+        .or_insert_with(|| Timer::new(DISCONNECT_DELAY, TimerMode::Once));
+}
+
+pub fn process_pending_disconnections(
+    mut server: ResMut<RenetServer>,
+    mut pending: ResMut<PendingDisconnections>,
+    time: Res<Time>,
+) {
+    pending.timers.retain(|client_id, timer| {
+        timer.tick(time.delta());
+        if timer.finished() {
+            server.disconnect(*client_id);
+        }
+        !timer.finished()
+    });
+}
@@ -1,77 +1,168 @@
-use crate::chat::{setup_chat_resources, ChatMessageEvent};
-use crate::init::{ServerLobby, TickCounter};
-use crate::player::handle_player_inputs;
+use crate::chat::{
+    broadcast_chat_message, send_chat_backlog, send_private_message, setup_chat_resources,
+    ChatHistory,
+};
+use crate::commands::{handle_commands, player_level, CommandEvent};
+use crate::disconnect::{disconnect_client, process_pending_disconnections, PendingDisconnections};
+use crate::entities::dropped_items::{DroppedItems, ItemDropEvent};
+use crate::entities::mobs::Mobs;
+use crate::entities::{self, ServerEntities};
+use crate::init::ServerLobby;
+use crate::inventory::{
+    apply_inventory_layout, craft_item, send_inventory, send_inventory_diff, send_inventory_slots,
+    PlayerInventories,
+};
+use crate::permissions::{ServerPermissions, LEVEL_ADMIN};
+use crate::player::{
+    handle_player_inputs, simulate_players_movement, PlayerMovement, PlayerMovements,
+};
 use crate::time::update_server_time;
+use crate::world;
+use crate::world::liquids::LiquidUpdates;
+use crate::world::loading::ChunkGenerationQueue;
+use crate::world::player_data::{PlayerData, PlayerDataStorage};
 use crate::world::save::SaveRequestEvent;
-use crate::world::BlockInteractionEvent;
-use crate::world::WorldUpdateRequestEvent;
-use crate::{chat, world};
+use crate::world::{BlockAction, BlockInteractionEvent, BreakingProgress};
+use crate::world::{ChunkView, ClientChunkViews};
 use bevy::prelude::*;
+use bevy_renet::renet::transport::NetcodeServerTransport;
 use bevy_renet::renet::{DefaultChannel, RenetServer, ServerEvent};
-use bincode::Options;
+use rand::random;
+use shared::auth::username_from_user_data;
 use shared::messages::{
-    AuthRegisterResponse, ChatConversation, ClientToServerMessage, PlayerSpawnEvent,
-    ServerToClientMessage,
+    decode_message, encode_message, AuthRegisterResponse, ClientToServerMessage, PlayerId,
+    PlayerSpawnEvent, ServerToClientMessage, MAX_CHAT_MESSAGE_LENGTH,
 };
-use shared::world::ServerWorldMap;
+use shared::world::{registry, BlockId, ItemId, ItemStack, ItemType, ServerWorldMap};
 use shared::GameServerConfig;
+use std::collections::HashMap;
+
+/// Position where new players appear
+pub(crate) const SPAWN_POSITION: Vec3 = Vec3::new(7.5, 80.0, 7.5);
+
+/// Saves the data of a leaving player, and forgets about their session.\
+/// Returns the items that did not fit back into their inventory, to be dropped where they left
+fn remove_player(
+    player_id: PlayerId,
+    lobby: &mut ServerLobby,
+    movements: &mut PlayerMovements,
+    world_map: &mut ServerWorldMap,
+    inventories: &mut PlayerInventories,
+    storage: &PlayerDataStorage,
+    views: &mut ClientChunkViews,
+) -> Vec<ItemDropEvent> {
+    let position = world_map.player_positions.remove(&player_id);
+    views.views.remove(&player_id);
+    let movement = movements.players.remove(&player_id);
+    lobby.session_tokens.remove(&player_id);
+    let Some(username) = lobby.players.remove(&player_id) else {
+        return Vec::new();
+    };
+    let (inventory, leftovers) = inventories.remove(&username);
 
-#[derive(Resource)]
-pub struct BroadcastTimer {
-    pub timer: Timer,
+    if let Some(movement) = movement {
+        if let Err(e) = storage.save(&username, &movement.to_data(inventory)) {
+            error!("Failed to save data of player {}: {}", username, e);
+        }
+    }
+
+    let Some(position) = position else {
+        return Vec::new();
+    };
+    leftovers
+        .into_iter()
+        .map(|stack| ItemDropEvent::thrown(position, Vec3::ZERO, stack))
+        .collect()
 }
 
 pub fn setup_resources_and_events(app: &mut App) {
-    app.insert_resource(BroadcastTimer {
-        timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-    })
-    .add_event::<WorldUpdateRequestEvent>()
-    .add_event::<SaveRequestEvent>()
-    .add_event::<BlockInteractionEvent>();
+    app.add_event::<SaveRequestEvent>()
+        .add_event::<BlockInteractionEvent>()
+        .add_event::<CommandEvent>()
+        .add_event::<ItemDropEvent>()
+        .insert_resource(PlayerMovements::default())
+        .insert_resource(ClientChunkViews::default())
+        .insert_resource(ChunkGenerationQueue::default())
+        .insert_resource(LiquidUpdates::default())
+        .insert_resource(BreakingProgress::default())
+        .insert_resource(ServerEntities::default())
+        .insert_resource(DroppedItems::default())
+        .insert_resource(Mobs::default())
+        .insert_resource(PendingDisconnections::default());
 
     setup_chat_resources(app);
 }
 
 pub fn register_systems(app: &mut App) {
-    app.add_systems(Update, server_update_system);
-
-    app.add_systems(Update, chat::broadcast_chat_messages);
+    app.add_systems(
+        Update,
+        (server_update_system, simulate_players_movement).chain(),
+    );
 
     app.add_systems(
         Update,
-        (world::broadcast_world_state, world::send_world_update),
+        (
+            world::broadcast_world_state,
+            world::loading::process_chunk_generation,
+            world::send_world_update,
+            world::broadcast_block_changes,
+        )
+            .chain(),
     );
 
-    app.add_systems(Update, world::save::save_world_system);
-    app.add_systems(Update, world::handle_block_interactions);
+    app.add_systems(
+        Update,
+        (handle_commands, world::save::save_world_system).chain(),
+    );
+    app.add_systems(
+        Update,
+        (
+            world::handle_block_interactions,
+            world::liquids::flow_liquids,
+            entities::dropped_items::update_dropped_items,
+            entities::mobs::spawn_mobs,
+            entities::mobs::update_mobs,
+            entities::replicate_entities,
+        )
+            .chain(),
+    );
 
     app.add_systems(Update, update_server_time);
+    app.add_systems(Update, process_pending_disconnections);
 }
 
 #[allow(clippy::type_complexity)]
 fn server_update_system(
     mut server_events: EventReader<ServerEvent>,
-    (mut server, mut chat_conversation, mut lobby, tick): (
+    (mut server, mut chat_history, mut lobby, mut movements): (
         ResMut<RenetServer>,
-        ResMut<ChatConversation>,
+        ResMut<ChatHistory>,
         ResMut<ServerLobby>,
-        Res<TickCounter>,
+        ResMut<PlayerMovements>,
     ),
     (
-        mut ev_chat,
         mut ev_app_exit,
-        mut ev_world_update_request,
         mut ev_save_request,
         mut ev_block_interaction,
+        mut ev_command,
+        mut ev_item_drop,
     ): (
-        EventWriter<ChatMessageEvent>,
         EventWriter<AppExit>,
-        EventWriter<WorldUpdateRequestEvent>,
         EventWriter<SaveRequestEvent>,
         EventWriter<BlockInteractionEvent>,
+        EventWriter<CommandEvent>,
+        EventWriter<ItemDropEvent>,
     ),
     config: Res<GameServerConfig>,
     mut world_map: ResMut<ServerWorldMap>,
+    (mut inventories, player_data, transport, permissions, mut pending, mut views): (
+        ResMut<PlayerInventories>,
+        Res<PlayerDataStorage>,
+        Res<NetcodeServerTransport>,
+        Res<ServerPermissions>,
+        ResMut<PendingDisconnections>,
+        ResMut<ClientChunkViews>,
+    ),
 ) {
     for event in server_events.read() {
         debug!("event received");
@@ -81,6 +172,16 @@ fn server_update_system(
             }
             ServerEvent::ClientDisconnected { client_id, reason } => {
                 info!("Player {} disconnected: {}", client_id, reason);
+                pending.remove(*client_id);
+                ev_item_drop.send_batch(remove_player(
+                    client_id.raw(),
+                    &mut lobby,
+                    &mut movements,
+                    &mut world_map,
+                    &mut inventories,
+                    &player_data,
+                    &mut views,
+                ));
             }
         }
     }
@@ -88,43 +189,134 @@ fn server_update_system(
     for client_id in server.clients_id() {
         while let Some(message) = server.receive_message(client_id, DefaultChannel::ReliableOrdered)
         {
-            let msg = bincode::options().deserialize::<ClientToServerMessage>(&message);
-            let msg = match msg {
+            // Clients on their way out have nothing more to say
+            if pending.contains(client_id) {
+                continue;
+            }
+
+            let msg = match decode_message::<ClientToServerMessage>(&message) {
                 Ok(msg) => msg,
                 Err(e) => {
-                    error!("Failed to parse incoming message: {}", e);
+                    warn!("Invalid message from client {}: {}", client_id, e);
+                    disconnect_client(&mut server, &mut pending, client_id, e.to_string());
                     continue;
                 }
             };
 
+            // Only authenticated players can do anything else than logging in
+            if !matches!(msg, ClientToServerMessage::AuthRegisterRequest(_))
+                && !lobby.players.contains_key(&client_id.raw())
+            {
+                debug!("Ignored message from unauthenticated client {}", client_id);
+                continue;
+            }
+
             match msg {
                 ClientToServerMessage::AuthRegisterRequest(auth_req) => {
                     info!("Auth request received {:?}", auth_req);
 
+                    // Loading the data of a player twice would duplicate their items
+                    if lobby.players.contains_key(&client_id.raw()) {
+                        warn!("Client {} tried to log in twice", client_id);
+                        continue;
+                    }
                     if lobby.players.values().any(|v| *v == auth_req.username) {
-                        debug!("Username already in map: {}", &auth_req.username);
-                        return;
+                        info!("Player {} is already connected", auth_req.username);
+                        disconnect_client(
+                            &mut server,
+                            &mut pending,
+                            client_id,
+                            "This player is already connected".into(),
+                        );
+                        continue;
+                    }
+
+                    if permissions.banned.contains(&auth_req.username) {
+                        info!("Banned player {} tried to join", auth_req.username);
+                        disconnect_client(
+                            &mut server,
+                            &mut pending,
+                            client_id,
+                            "You are banned from this server".into(),
+                        );
+                        continue;
                     }
 
-                    // let new_session_token = generate_session_token();
+                    // With connect tokens, the username is part of the token issued to the player
+                    if config.private_key.is_some() {
+                        let token_username = transport
+                            .user_data(client_id)
+                            .and_then(|user_data| username_from_user_data(&user_data));
+                        if token_username.as_ref() != Some(&auth_req.username) {
+                            warn!(
+                                "Client {} tried to log in as {} with a token issued for {:?}",
+                                client_id, auth_req.username, token_username
+                            );
+                            disconnect_client(
+                                &mut server,
+                                &mut pending,
+                                client_id,
+                                "Your connect token was issued for another player".into(),
+                            );
+                            continue;
+                        }
+                    }
+
+                    // Players who already joined the world get back where they left.\
+                    // Starting over would overwrite their file when they leave, so they can't join
+                    let data = match player_data.load(&auth_req.username) {
+                        Ok(data) => data.unwrap_or_else(|| PlayerData::new(SPAWN_POSITION)),
+                        Err(e) => {
+                            error!("Failed to load data of player {}: {}", auth_req.username, e);
+                            disconnect_client(
+                                &mut server,
+                                &mut pending,
+                                client_id,
+                                "Your player data could not be loaded".into(),
+                            );
+                            continue;
+                        }
+                    };
+
+                    let session_token = generate_session_token();
                     lobby
                         .players
                         .insert(client_id.raw(), auth_req.username.clone());
+                    lobby.session_tokens.insert(client_id.raw(), session_token);
                     debug!("New lobby : {:?}", lobby);
 
+                    let mut movement = PlayerMovement::new(&data);
+                    // Only admins can fly, they may have lost their level since they left
+                    if player_level(&permissions, &config, &auth_req.username) < LEVEL_ADMIN {
+                        movement.state.is_flying = false;
+                    }
+                    movements.players.insert(client_id.raw(), movement);
+                    world_map
+                        .player_positions
+                        .insert(client_id.raw(), data.position);
+                    views.views.insert(
+                        client_id.raw(),
+                        ChunkView::new(auth_req.render_distance, auth_req.vertical_render_distance),
+                    );
+                    inventories
+                        .inventories
+                        .insert(auth_req.username.clone(), data.inventory);
+
                     let spawn_message = PlayerSpawnEvent {
                         id: client_id.raw(),
                         name: auth_req.username,
-                        position: Vec3::new(0.0, 80.0, 0.0),
+                        position: data.position,
+                        direction: data.look_direction,
                     };
 
                     // TODO: add cleanup system if no heartbeat
-                    let msg = &(AuthRegisterResponse {
+                    let msg = ServerToClientMessage::AuthRegisterResponse(AuthRegisterResponse {
                         username: spawn_message.name.clone(),
-                        session_token: client_id.raw() as u128,
+                        session_token,
                         spawn_event: spawn_message.clone(),
+                        registry: Box::new(registry().clone()),
                     });
-                    let auth_response_payload = bincode::options().serialize(msg).unwrap();
+                    let auth_response_payload = encode_message(&msg);
 
                     server.send_message(
                         client_id,
@@ -132,74 +324,126 @@ fn server_update_system(
                         auth_response_payload,
                     );
 
+                    send_chat_backlog(&mut server, client_id, &chat_history);
+
+                    let inventory = inventories.get_or_create(&spawn_message.name);
+                    send_inventory(&mut server, client_id, inventory);
+
                     for (id, name) in lobby.players.iter() {
+                        let Some(movement) = movements.players.get(id) else {
+                            continue;
+                        };
                         let spawn_message = PlayerSpawnEvent {
                             id: *id,
                             name: name.into(),
-                            position: Vec3::new(0.0, 80.0, 0.0),
+                            position: movement.state.position,
+                            direction: movement.look_direction,
                         };
 
                         let spawn_message_wrapped =
                             &ServerToClientMessage::PlayerSpawn(spawn_message);
 
-                        let spawn_payload = bincode::options()
-                            .serialize(&spawn_message_wrapped.clone())
-                            .unwrap();
+                        let spawn_payload = encode_message(&spawn_message_wrapped.clone());
 
                         server.broadcast_message(DefaultChannel::ReliableUnordered, spawn_payload);
                         info!("Sending spawn order {:?}", spawn_message_wrapped);
                     }
                 }
-                ClientToServerMessage::ChatMessage(chat_msg) => {
-                    info!("Chat message received: {:?}", &chat_msg);
-                    chat_conversation.messages.push(chat_msg);
-                    ev_chat.send(ChatMessageEvent);
+                ClientToServerMessage::ChatMessage(content) => {
+                    info!("Chat message received: {:?}", &content);
+                    let content = content.trim();
+                    if content.is_empty() || content.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
+                        debug!("Ignored invalid chat message of {}", client_id);
+                        continue;
+                    }
+                    if let Some(command) = content.strip_prefix('/') {
+                        ev_command.send(CommandEvent {
+                            player_id: client_id.raw(),
+                            command: command.to_string(),
+                        });
+                        continue;
+                    }
+                    // The author is the player the message comes from, not what the client says
+                    let author_name = lobby.players[&client_id.raw()].clone();
+                    broadcast_chat_message(
+                        &mut server,
+                        &lobby,
+                        &mut chat_history,
+                        &author_name,
+                        content.to_string(),
+                    );
                 }
                 ClientToServerMessage::Exit(order) => {
                     debug!("Received shutdown order... {:?}", order);
-                    // TODO: add permission checks
+                    if !lobby.is_valid_session(client_id.raw(), order.session_token) {
+                        warn!(
+                            "Ignored exit order of {} with an invalid session",
+                            client_id
+                        );
+                        continue;
+                    }
+                    ev_item_drop.send_batch(remove_player(
+                        client_id.raw(),
+                        &mut lobby,
+                        &mut movements,
+                        &mut world_map,
+                        &mut inventories,
+                        &player_data,
+                        &mut views,
+                    ));
+                    // Players can always leave, only the host of a solo game stops the server
                     if config.is_solo {
                         info!("Server is going down...");
                         ev_app_exit.send(AppExit::Success);
                     } else {
                         server.disconnect(client_id);
-                        lobby.players.remove(&(order.session_token as u64));
                         info!("Player {:?} disconnected", client_id);
                     }
                 }
                 ClientToServerMessage::PlayerInputs(inputs) => {
-                    handle_player_inputs(inputs, &tick);
+                    let can_fly = lobby.players.get(&client_id.raw()).is_some_and(|username| {
+                        player_level(&permissions, &config, username) >= LEVEL_ADMIN
+                    });
+                    handle_player_inputs(client_id.raw(), inputs, can_fly, &mut movements);
                 }
                 ClientToServerMessage::SaveWorldRequest(save_req) => {
-                    debug!(
-                        "Save request received from client with session token: {}",
-                        save_req.session_token
-                    );
+                    if !lobby.is_valid_session(client_id.raw(), save_req.session_token) {
+                        warn!(
+                            "Ignored save request of {} with an invalid session",
+                            client_id
+                        );
+                        continue;
+                    }
+                    let username = &lobby.players[&client_id.raw()];
+                    if player_level(&permissions, &config, username) < LEVEL_ADMIN {
+                        send_private_message(
+                            &mut server,
+                            &mut chat_history,
+                            client_id,
+                            "You are not allowed to save the world".into(),
+                        );
+                        continue;
+                    }
+                    debug!("Save request received from client {}", client_id);
 
                     ev_save_request.send(SaveRequestEvent);
                 }
                 ClientToServerMessage::WorldUpdateRequest {
-                    player_chunk_position,
-                    requested_chunks,
                     render_distance,
+                    vertical_render_distance,
                 } => {
                     debug!(
-                        "Received WorldUpdateRequest: client_id = {}, player_chunk_position = {:?}, render_distance = {}, requested_chunks = {}",
-                        client_id,
-                        player_chunk_position,
-                        render_distance,
-                        requested_chunks.len(),
+                        "Received WorldUpdateRequest: client_id = {}, render_distance = {}, vertical_render_distance = {}",
+                        client_id, render_distance, vertical_render_distance,
                     );
-                    ev_world_update_request.send(WorldUpdateRequestEvent {
-                        render_distance,
-                        client: client_id,
-                        chunks: requested_chunks,
-                        player_chunk_position,
-                    });
+                    if let Some(view) = views.views.get_mut(&client_id.raw()) {
+                        view.set_render_distance(render_distance, vertical_render_distance);
+                    }
                 }
                 ClientToServerMessage::BlockInteraction {
                     position,
                     block_type,
+                    slot,
                 } => {
                     debug!(
                         "Block interaction received at {:?}: {:?}",
@@ -207,19 +451,114 @@ fn server_update_system(
                     );
 
                     ev_block_interaction.send(BlockInteractionEvent {
+                        player_id: client_id.raw(),
+                        position,
+                        action: match block_type {
+                            Some(block) => BlockAction::Place { block, slot },
+                            None => BlockAction::Break { slot },
+                        },
+                    });
+                }
+                ClientToServerMessage::StartBreaking { position } => {
+                    ev_block_interaction.send(BlockInteractionEvent {
+                        player_id: client_id.raw(),
                         position,
-                        block_type,
+                        action: BlockAction::StartBreaking,
                     });
                 }
-                ClientToServerMessage::SetPlayerPosition { position } => {
-                    world_map.player_positions.insert(client_id.raw(), position);
+                ClientToServerMessage::AbortBreaking { position } => {
+                    ev_block_interaction.send(BlockInteractionEvent {
+                        player_id: client_id.raw(),
+                        position,
+                        action: BlockAction::AbortBreaking,
+                    });
+                }
+                ClientToServerMessage::InventoryLayout(layout) => {
+                    let Some(username) = lobby.players.get(&client_id.raw()) else {
+                        continue;
+                    };
+                    if !apply_inventory_layout(&mut inventories, username, layout) {
+                        info!("Rejected inventory layout of player {}", username);
+                        // The client drops its held stack when receiving a full inventory
+                        if let (Some(stack), Some(position)) = (
+                            inventories.release_held_stack(username),
+                            world_map.player_positions.get(&client_id.raw()),
+                        ) {
+                            ev_item_drop.send(ItemDropEvent::thrown(*position, Vec3::ZERO, stack));
+                        }
+                        let inventory = inventories.get_or_create(username);
+                        send_inventory(&mut server, client_id, inventory);
+                    }
+                }
+                ClientToServerMessage::CraftItem { table } => {
+                    let Some(username) = lobby.players.get(&client_id.raw()) else {
+                        continue;
+                    };
+                    let inventory = inventories.get_or_create(username);
+                    let previous = inventory.clone();
+                    // Nothing is predicted by the client, a rejected craft only has to be logged
+                    if let Err(reason) = craft_item(inventory, &world_map, client_id.raw(), table) {
+                        info!("Rejected craft of player {}: {}", username, reason);
+                        continue;
+                    }
+                    send_inventory_diff(&mut server, client_id, inventory, &previous);
+                }
+                ClientToServerMessage::DropItem { slot } => {
+                    let (Some(username), Some(position), Some(movement)) = (
+                        lobby.players.get(&client_id.raw()),
+                        world_map.player_positions.get(&client_id.raw()),
+                        movements.players.get(&client_id.raw()),
+                    ) else {
+                        continue;
+                    };
+                    let inventory = inventories.get_or_create(username);
+                    let Some(stack) = inventory.inner.get(&slot).copied() else {
+                        // The client predicted the drop, its slot is set back
+                        send_inventory_slots(&mut server, client_id, HashMap::from([(slot, None)]));
+                        continue;
+                    };
+                    inventory.remove_item_from_stack(slot, 1);
+                    ev_item_drop.send(ItemDropEvent::thrown(
+                        *position,
+                        movement.look_direction,
+                        ItemStack { nb: 1, ..stack },
+                    ));
+                }
+                ClientToServerMessage::DebugGetBlocks => {
+                    // Debug items are only available in solo games
+                    if !config.is_solo {
+                        continue;
+                    }
+                    let (Some(username), Some(&position)) = (
+                        lobby.players.get(&client_id.raw()),
+                        world_map.player_positions.get(&client_id.raw()),
+                    ) else {
+                        continue;
+                    };
+                    let inventory = inventories.get_or_create(username);
+                    let previous = inventory.clone();
+                    for (item_id, block_id) in [
+                        (ItemId::GLASS, BlockId::GLASS),
+                        (ItemId::POPPY, BlockId::POPPY),
+                        (ItemId::DANDELION, BlockId::DANDELION),
+                    ] {
+                        let leftover = inventory.add_item_to_inventory(ItemStack {
+                            item_id,
+                            item_type: ItemType::Block(block_id),
+                            nb: 64,
+                        });
+                        if let Some(stack) = leftover {
+                            ev_item_drop.send(ItemDropEvent::thrown(position, Vec3::ZERO, stack));
+                        }
+                    }
+                    send_inventory_diff(&mut server, client_id, inventory, &previous);
                 }
             }
         }
     }
 }
 
-// fn generate_session_token() -> u128 {
-//     let random_value: u128 = random();
-//     random_value
-// }
+fn generate_session_token() -> u128 {
+    let random_value: u128 = random();
+    random_value
+}
@@ -0,0 +1,169 @@
+use crate::entities::ServerEntities;
+use crate::init::ServerLobby;
+use crate::inventory::{send_inventory_diff, PlayerInventories};
+use crate::world::chunk_of;
+use crate::world::loading::ChunkGenerationQueue;
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetServer};
+use shared::entities::{step_entity_physics, EntityId, EntityKind, EntityMotion};
+use shared::players::{FALL_LIMIT, PLAYER_HEIGHT};
+use shared::world::{ItemStack, ServerWorldMap};
+use std::collections::HashMap;
+
+/// How quickly the items sliding on the ground stop
+const GROUND_FRICTION: f32 = 8.0;
+/// Below this horizontal speed, the items stop sliding
+const MIN_SLIDING_SPEED: f32 = 0.05;
+/// Speed of the items thrown by the players, in blocks per second
+const THROW_SPEED: f32 = 6.0;
+/// Players pick up the items within this distance of their center
+const PICKUP_RADIUS: f32 = 1.5;
+/// Time before thrown items can be picked up, so that they are not picked up right back, in seconds
+const THROWN_PICKUP_DELAY: f32 = 2.0;
+/// Time before the drops of a broken block can be picked up, in seconds
+const BLOCK_DROP_PICKUP_DELAY: f32 = 0.5;
+/// Items lying in the world for this long disappear, in seconds
+const DESPAWN_DELAY: f32 = 300.0;
+
+/// Items appearing in the world
+#[derive(Event, Debug)]
+pub struct ItemDropEvent {
+    pub stack: ItemStack,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Time before the items can be picked up, in seconds
+    pub pickup_delay: f32,
+}
+
+impl ItemDropEvent {
+    /// Items thrown by a player toward `direction`, from the height of their eyes.\
+    /// With a zero `direction`, the items just fall at their feet
+    pub fn thrown(player_position: Vec3, direction: Vec3, stack: ItemStack) -> Self {
+        Self {
+            stack,
+            position: player_position + Vec3::Y * (PLAYER_HEIGHT / 2. - 0.2),
+            velocity: direction.normalize_or_zero() * THROW_SPEED + Vec3::Y * 2.,
+            pickup_delay: THROWN_PICKUP_DELAY,
+        }
+    }
+
+    /// Items popping out of the block broken at `position`
+    pub fn from_block(position: IVec3, stack: ItemStack) -> Self {
+        Self {
+            stack,
+            position: position.as_vec3() + Vec3::splat(0.5),
+            velocity: Vec3::new(
+                rand::random::<f32>() * 2. - 1.,
+                3.,
+                rand::random::<f32>() * 2. - 1.,
+            ),
+            pickup_delay: BLOCK_DROP_PICKUP_DELAY,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    /// Time since the item appeared, in seconds
+    pub age: f32,
+    pub pickup_delay: f32,
+}
+
+/// Items lying in the world, by entity
+#[derive(Resource, Default, Debug)]
+pub struct DroppedItems {
+    pub items: HashMap<EntityId, DroppedItem>,
+}
+
+/// Spawns the dropped items, makes them fall and lets the players pick them up
+#[allow(clippy::too_many_arguments)]
+pub fn update_dropped_items(
+    mut server: ResMut<RenetServer>,
+    mut entities: ResMut<ServerEntities>,
+    mut dropped: ResMut<DroppedItems>,
+    mut inventories: ResMut<PlayerInventories>,
+    world_map: Res<ServerWorldMap>,
+    queue: Res<ChunkGenerationQueue>,
+    lobby: Res<ServerLobby>,
+    time: Res<Time>,
+    mut events: EventReader<ItemDropEvent>,
+) {
+    for event in events.read() {
+        let id = entities.spawn(
+            EntityKind::DroppedItem(event.stack.item_id),
+            EntityMotion {
+                position: event.position,
+                velocity: event.velocity,
+                rotation: 0.,
+            },
+        );
+        dropped.items.insert(
+            id,
+            DroppedItem {
+                stack: event.stack,
+                age: 0.,
+                pickup_delay: event.pickup_delay,
+            },
+        );
+    }
+
+    let delta = time.delta_seconds();
+    dropped.items.retain(|id, item| {
+        let Some(entity) = entities.get_mut(*id) else {
+            return false;
+        };
+        let mut motion = *entity.motion();
+
+        item.age += delta;
+        if item.age > DESPAWN_DELAY || motion.position.y < FALL_LIMIT {
+            entities.despawn(*id);
+            return false;
+        }
+
+        // Items wait until the terrain of their chunk is known. Empty chunks are never in the map,
+        // and are generated again when a player comes back near them
+        if queue.is_ready(&world_map, &chunk_of(&motion.position)) {
+            let half_extents = entity.kind.half_extents();
+            if step_entity_physics(&mut motion, half_extents, &*world_map, delta) {
+                let friction = (-GROUND_FRICTION * delta).exp();
+                motion.velocity.x *= friction;
+                motion.velocity.z *= friction;
+                if motion.velocity.xz().length() < MIN_SLIDING_SPEED {
+                    motion.velocity.x = 0.;
+                    motion.velocity.z = 0.;
+                }
+            }
+            entity.set_motion(motion);
+        }
+        if item.age < item.pickup_delay {
+            return true;
+        }
+
+        for (player_id, player_position) in world_map.player_positions.iter() {
+            if motion.position.distance(*player_position) > PICKUP_RADIUS {
+                continue;
+            }
+            let Some(username) = lobby.players.get(player_id) else {
+                continue;
+            };
+            let inventory = inventories.get_or_create(username);
+            let previous = inventory.clone();
+            let leftover = inventory.add_item_to_inventory(item.stack);
+            send_inventory_diff(
+                &mut server,
+                ClientId::from_raw(*player_id),
+                inventory,
+                &previous,
+            );
+            match leftover {
+                None => {
+                    entities.despawn(*id);
+                    return false;
+                }
+                Some(leftover) => item.stack = leftover,
+            }
+        }
+        true
+    });
+}
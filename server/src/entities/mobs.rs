@@ -0,0 +1,342 @@
+use crate::entities::pathfinding::{can_stand_at, find_path};
+use crate::entities::ServerEntities;
+use crate::init::{ServerTime, TickCounter};
+use crate::world::chunk_of;
+use crate::world::generation::TerrainNoise;
+use crate::world::loading::ChunkGenerationQueue;
+use bevy::prelude::*;
+use rand::Rng;
+use shared::entities::{
+    get_mob_definition, step_entity_physics, EntityId, EntityKind, EntityMotion, MobBehavior,
+    MobDefinition, MobKind, SpawnTime, MOB_KINDS,
+};
+use shared::is_night;
+use shared::messages::PlayerId;
+use shared::players::{FALL_LIMIT, JUMP_VELOCITY};
+use shared::world::{ServerWorldMap, WorldSeed};
+use std::collections::{HashMap, VecDeque};
+
+/// Ticks between two attempts to spawn mobs around each player
+const MOB_SPAWN_INTERVAL: u64 = 60;
+/// Mobs appear between these horizontal distances of a player
+const MIN_SPAWN_DISTANCE: f32 = 24.0;
+const MAX_SPAWN_DISTANCE: f32 = 40.0;
+/// Blocks above and below a player where mobs can appear
+const SPAWN_HEIGHT_RANGE: i32 = 16;
+/// Mobs within this distance of a player count toward their limit around them
+const MOB_COUNT_DISTANCE: f32 = 64.0;
+/// Mobs farther than this from every player disappear
+const DESPAWN_DISTANCE: f32 = 80.0;
+/// Mobs out of their part of the day disappear once this far from every player
+const OUT_OF_TIME_DESPAWN_DISTANCE: f32 = 24.0;
+/// Hostile mobs chase the players within this distance
+const CHASE_DISTANCE: f32 = 16.0;
+/// Hostile mobs stop this close to the player they chase
+const CHASE_STOP_DISTANCE: f32 = 1.2;
+/// Time between two searches of a path toward the chased player, in seconds
+const CHASE_REPATH_DELAY: f32 = 1.0;
+/// Mobs wander to positions up to this many blocks away
+const WANDER_DISTANCE: i32 = 8;
+/// Time mobs stand still between two walks, in seconds
+const MIN_IDLE_TIME: f32 = 2.0;
+const MAX_IDLE_TIME: f32 = 6.0;
+/// Mobs move on to the next position of their path once this close to it
+const WAYPOINT_DISTANCE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MobState {
+    Idle,
+    Wandering,
+    Chasing(PlayerId),
+}
+
+#[derive(Debug)]
+pub struct Mob {
+    pub kind: MobKind,
+    state: MobState,
+    /// Positions of the feet the mob walks through
+    path: VecDeque<IVec3>,
+    /// Time left before the mob makes its next decision, in seconds
+    timer: f32,
+    on_ground: bool,
+}
+
+impl Mob {
+    fn new(kind: MobKind) -> Self {
+        Self {
+            kind,
+            state: MobState::Idle,
+            path: VecDeque::new(),
+            timer: 0.,
+            on_ground: false,
+        }
+    }
+}
+
+/// Mobs living in the world, by entity
+#[derive(Resource, Default, Debug)]
+pub struct Mobs {
+    pub mobs: HashMap<EntityId, Mob>,
+}
+
+/// Whether mobs of this definition appear at the given time
+fn is_spawn_time(definition: &MobDefinition, time: u64) -> bool {
+    match definition.spawn_time {
+        SpawnTime::Day => !is_night(time),
+        SpawnTime::Night => is_night(time),
+    }
+}
+
+/// Height of the mob, in blocks
+fn mob_height(definition: &MobDefinition) -> i32 {
+    (definition.half_extents.y * 2.).ceil() as i32
+}
+
+/// Block the feet of the mob are in
+fn feet_position(motion: &EntityMotion, definition: &MobDefinition) -> IVec3 {
+    (motion.position - Vec3::Y * (definition.half_extents.y - 0.01))
+        .floor()
+        .as_ivec3()
+}
+
+/// Highest position of the column where a mob can stand, between `top` and `bottom`
+fn find_ground(
+    world_map: &ServerWorldMap,
+    column: IVec3,
+    top: i32,
+    bottom: i32,
+    height: i32,
+) -> Option<IVec3> {
+    (bottom..=top)
+        .rev()
+        .map(|y| column.with_y(y))
+        .find(|position| {
+            can_stand_at(world_map, *position, height)
+                && world_map
+                    .get_block_by_coordinates(position)
+                    .is_none_or(|block| !block.id.is_liquid())
+        })
+}
+
+/// Tries to spawn mobs around each player, in the biomes and at the time of the day they live in
+pub fn spawn_mobs(
+    mut entities: ResMut<ServerEntities>,
+    mut mobs: ResMut<Mobs>,
+    world_map: Res<ServerWorldMap>,
+    (server_time, seed, ticker): (Res<ServerTime>, Res<WorldSeed>, Res<TickCounter>),
+    mut noise: Local<Option<TerrainNoise>>,
+) {
+    if ticker.tick % MOB_SPAWN_INTERVAL != 0 {
+        return;
+    }
+    let noise = noise.get_or_insert_with(|| TerrainNoise::new(seed.0));
+    let mut rng = rand::thread_rng();
+
+    for player_position in world_map.player_positions.values() {
+        for kind in MOB_KINDS {
+            let definition = get_mob_definition(kind);
+            if !is_spawn_time(&definition, server_time.0) {
+                continue;
+            }
+            let around = mobs
+                .mobs
+                .iter()
+                .filter(|(id, mob)| {
+                    mob.kind == kind
+                        && entities.get(**id).is_some_and(|entity| {
+                            entity.motion().position.distance(*player_position) < MOB_COUNT_DISTANCE
+                        })
+                })
+                .count();
+            if around >= definition.max_around_player {
+                continue;
+            }
+
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(MIN_SPAWN_DISTANCE..MAX_SPAWN_DISTANCE);
+            let column = (*player_position + Vec3::new(angle.cos(), 0., angle.sin()) * distance)
+                .floor()
+                .as_ivec3();
+            if !definition
+                .biomes
+                .contains(&noise.biome_at(column.x, column.z))
+            {
+                continue;
+            }
+            let Some(feet) = find_ground(
+                &world_map,
+                column,
+                column.y + SPAWN_HEIGHT_RANGE,
+                column.y - SPAWN_HEIGHT_RANGE,
+                mob_height(&definition),
+            ) else {
+                continue;
+            };
+            // Mobs never appear right next to a player
+            let position = feet.as_vec3() + Vec3::new(0.5, definition.half_extents.y, 0.5);
+            if world_map
+                .player_positions
+                .values()
+                .any(|player| player.distance(position) < MIN_SPAWN_DISTANCE)
+            {
+                continue;
+            }
+
+            let id = entities.spawn(
+                EntityKind::Mob(kind),
+                EntityMotion {
+                    position,
+                    velocity: Vec3::ZERO,
+                    rotation: rng.gen_range(0.0..std::f32::consts::TAU),
+                },
+            );
+            mobs.mobs.insert(id, Mob::new(kind));
+            debug!("Spawned {:?} at {:?}", kind, position);
+        }
+    }
+}
+
+/// Makes the mob choose what to do next, once its timer runs out
+fn think(
+    mob: &mut Mob,
+    motion: &EntityMotion,
+    definition: &MobDefinition,
+    world_map: &ServerWorldMap,
+    rng: &mut impl Rng,
+) {
+    let feet = feet_position(motion, definition);
+    let height = mob_height(definition);
+
+    if definition.behavior == MobBehavior::Hostile {
+        let target = world_map
+            .player_positions
+            .iter()
+            .map(|(id, position)| (id, position, position.distance(motion.position)))
+            .filter(|(_, _, distance)| *distance < CHASE_DISTANCE)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((player_id, player_position, distance)) = target {
+            mob.state = MobState::Chasing(*player_id);
+            mob.timer = CHASE_REPATH_DELAY;
+            mob.path.clear();
+            if distance > CHASE_STOP_DISTANCE {
+                // The player stands on the block below their feet
+                let goal = (*player_position - Vec3::Y * 0.5).floor().as_ivec3();
+                if let Some(path) = find_path(world_map, feet, goal, height) {
+                    mob.path = path.into();
+                }
+            }
+            return;
+        }
+    }
+
+    match mob.state {
+        MobState::Idle => {
+            let column = feet
+                + IVec3::new(
+                    rng.gen_range(-WANDER_DISTANCE..=WANDER_DISTANCE),
+                    0,
+                    rng.gen_range(-WANDER_DISTANCE..=WANDER_DISTANCE),
+                );
+            let goal = find_ground(world_map, column, feet.y + 3, feet.y - 4, height);
+            match goal.and_then(|goal| find_path(world_map, feet, goal, height)) {
+                Some(path) => {
+                    mob.state = MobState::Wandering;
+                    mob.path = path.into();
+                }
+                None => mob.timer = rng.gen_range(MIN_IDLE_TIME..MAX_IDLE_TIME),
+            }
+        }
+        // The chased player is gone, or the walk is over
+        MobState::Wandering | MobState::Chasing(_) => {
+            mob.state = MobState::Idle;
+            mob.path.clear();
+            mob.timer = rng.gen_range(MIN_IDLE_TIME..MAX_IDLE_TIME);
+        }
+    }
+}
+
+/// Steers the mob along its path, jumping up the blocks on its way
+fn walk(mob: &mut Mob, motion: &mut EntityMotion, definition: &MobDefinition) {
+    let feet = feet_position(motion, definition);
+    while let Some(waypoint) = mob.path.front() {
+        let target = Vec2::new(waypoint.x as f32 + 0.5, waypoint.z as f32 + 0.5);
+        let direction = target - motion.position.xz();
+        if direction.length() < WAYPOINT_DISTANCE && waypoint.y <= feet.y {
+            mob.path.pop_front();
+            continue;
+        }
+
+        let velocity = direction.normalize_or_zero() * definition.speed;
+        motion.velocity.x = velocity.x;
+        motion.velocity.z = velocity.y;
+        motion.rotation = velocity.x.atan2(velocity.y);
+        if waypoint.y > feet.y && mob.on_ground {
+            motion.velocity.y = JUMP_VELOCITY;
+        }
+        return;
+    }
+
+    motion.velocity.x = 0.;
+    motion.velocity.z = 0.;
+}
+
+/// Moves the mobs following what they decided to do, and despawns the ones far from every player
+pub fn update_mobs(
+    mut entities: ResMut<ServerEntities>,
+    mut mobs: ResMut<Mobs>,
+    world_map: Res<ServerWorldMap>,
+    queue: Res<ChunkGenerationQueue>,
+    server_time: Res<ServerTime>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    let mut rng = rand::thread_rng();
+
+    mobs.mobs.retain(|id, mob| {
+        let Some(entity) = entities.get_mut(*id) else {
+            return false;
+        };
+        let definition = get_mob_definition(mob.kind);
+        let mut motion = *entity.motion();
+
+        let nearest_player = world_map
+            .player_positions
+            .values()
+            .map(|position| position.distance(motion.position))
+            .min_by(f32::total_cmp);
+        let despawned = match nearest_player {
+            None => true,
+            Some(distance) => {
+                distance > DESPAWN_DISTANCE
+                    || (distance > OUT_OF_TIME_DESPAWN_DISTANCE
+                        && !is_spawn_time(&definition, server_time.0))
+            }
+        };
+        if despawned || motion.position.y < FALL_LIMIT {
+            entities.despawn(*id);
+            return false;
+        }
+
+        // Mobs freeze until the terrain of their chunk is known. Empty chunks are never in the map,
+        // and are generated again when a player comes back near them
+        if !queue.is_ready(&world_map, &chunk_of(&motion.position)) {
+            return true;
+        }
+
+        if let MobState::Chasing(player_id) = mob.state {
+            if !world_map.player_positions.contains_key(&player_id) {
+                mob.timer = 0.;
+            }
+        }
+        mob.timer -= delta;
+        if mob.timer <= 0. || (mob.state == MobState::Wandering && mob.path.is_empty()) {
+            think(mob, &motion, &definition, &world_map, &mut rng);
+        }
+
+        walk(mob, &mut motion, &definition);
+        mob.on_ground =
+            step_entity_physics(&mut motion, definition.half_extents, &*world_map, delta);
+        entity.set_motion(motion);
+        true
+    });
+}
@@ -0,0 +1,144 @@
+pub mod dropped_items;
+pub mod mobs;
+mod pathfinding;
+
+use crate::init::TickCounter;
+use crate::world::{chunk_of, ClientChunkViews};
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
+use shared::entities::{EntityId, EntityKind, EntityMotion, NetworkEntity};
+use shared::messages::{encode_message, EntityMessage, PlayerId, ServerToClientMessage};
+use std::collections::{HashMap, HashSet};
+
+/// Ticks between two updates of the entities sent to the clients
+const ENTITY_REPLICATION_INTERVAL: u64 = 3;
+
+#[derive(Debug)]
+pub struct ServerEntity {
+    pub kind: EntityKind,
+    motion: EntityMotion,
+    /// Whether the motion changed since it was last sent
+    moved: bool,
+}
+
+impl ServerEntity {
+    pub fn motion(&self) -> &EntityMotion {
+        &self.motion
+    }
+
+    pub fn set_motion(&mut self, motion: EntityMotion) {
+        if motion != self.motion {
+            self.motion = motion;
+            self.moved = true;
+        }
+    }
+}
+
+/// Entities simulated by the server, replicated to the clients holding their chunk.\
+/// What the entities do is up to the systems of their kind, which keep their own data by `EntityId`
+#[derive(Resource, Default, Debug)]
+pub struct ServerEntities {
+    next_id: EntityId,
+    entities: HashMap<EntityId, ServerEntity>,
+    /// Entities each client was told about
+    known: HashMap<PlayerId, HashSet<EntityId>>,
+}
+
+impl ServerEntities {
+    pub fn spawn(&mut self, kind: EntityKind, motion: EntityMotion) -> EntityId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entities.insert(
+            id,
+            ServerEntity {
+                kind,
+                motion,
+                moved: true,
+            },
+        );
+        id
+    }
+
+    /// The clients are told about it on the next update
+    pub fn despawn(&mut self, id: EntityId) -> Option<ServerEntity> {
+        self.entities.remove(&id)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&ServerEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut ServerEntity> {
+        self.entities.get_mut(&id)
+    }
+}
+
+fn send_entity_message(server: &mut RenetServer, player_id: PlayerId, message: EntityMessage) {
+    let payload = encode_message(&ServerToClientMessage::Entities(message));
+    server.send_message(
+        ClientId::from_raw(player_id),
+        DefaultChannel::ReliableOrdered,
+        payload,
+    );
+}
+
+/// Tells each client about the entities entering, moving in and leaving the chunks it holds
+pub fn replicate_entities(
+    mut server: ResMut<RenetServer>,
+    mut entities: ResMut<ServerEntities>,
+    views: Res<ClientChunkViews>,
+    ticker: Res<TickCounter>,
+) {
+    if ticker.tick % ENTITY_REPLICATION_INTERVAL != 0 {
+        return;
+    }
+
+    let ServerEntities {
+        entities, known, ..
+    } = &mut *entities;
+    // Forget the players who left
+    known.retain(|player_id, _| views.views.contains_key(player_id));
+
+    for (player_id, view) in views.views.iter() {
+        let is_visible =
+            |entity: &ServerEntity| view.loaded.contains(&chunk_of(&entity.motion.position));
+        let known_entities = known.entry(*player_id).or_default();
+
+        let mut despawned = Vec::new();
+        known_entities.retain(|id| {
+            let visible = entities.get(id).is_some_and(is_visible);
+            if !visible {
+                despawned.push(*id);
+            }
+            visible
+        });
+
+        let mut spawned = Vec::new();
+        let mut updated = Vec::new();
+        for (id, entity) in entities.iter().filter(|(_, entity)| is_visible(entity)) {
+            if known_entities.insert(*id) {
+                spawned.push(NetworkEntity {
+                    id: *id,
+                    kind: entity.kind,
+                    motion: entity.motion,
+                });
+            } else if entity.moved {
+                updated.push((*id, entity.motion));
+            }
+        }
+
+        if !despawned.is_empty() {
+            send_entity_message(&mut server, *player_id, EntityMessage::Despawn(despawned));
+        }
+        if !spawned.is_empty() {
+            send_entity_message(&mut server, *player_id, EntityMessage::Spawn(spawned));
+        }
+        if !updated.is_empty() {
+            send_entity_message(&mut server, *player_id, EntityMessage::Update(updated));
+        }
+    }
+
+    for entity in entities.values_mut() {
+        entity.moved = false;
+    }
+}
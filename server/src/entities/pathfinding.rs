@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use shared::world::WorldMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Most positions explored when looking for a path, so that unreachable goals are given up quickly
+const MAX_EXPLORED_POSITIONS: usize = 1024;
+
+const HORIZONTAL_OFFSETS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+/// Steps a mob can take: one block up, on the same level, or down a small drop
+const VERTICAL_STEPS: [i32; 4] = [0, 1, -1, -2];
+
+fn is_solid(world_map: &impl WorldMap, position: IVec3) -> bool {
+    world_map
+        .get_block_by_coordinates(&position)
+        .is_some_and(|block| block.id.has_hitbox())
+}
+
+/// Whether the blocks of a column between `bottom` and `top`, included, are all empty
+fn is_clear(world_map: &impl WorldMap, column: IVec3, bottom: i32, top: i32) -> bool {
+    (bottom..=top).all(|y| !is_solid(world_map, column.with_y(y)))
+}
+
+/// Whether a mob `height` blocks tall can stand with its feet in `position`
+pub fn can_stand_at(world_map: &impl WorldMap, position: IVec3, height: i32) -> bool {
+    is_solid(world_map, position + IVec3::NEG_Y)
+        && is_clear(world_map, position, position.y, position.y + height - 1)
+}
+
+/// Positions a mob standing at `position` can walk to
+fn neighbours(
+    world_map: &impl WorldMap,
+    position: IVec3,
+    height: i32,
+) -> impl Iterator<Item = IVec3> + '_ {
+    HORIZONTAL_OFFSETS.iter().flat_map(move |offset| {
+        VERTICAL_STEPS.iter().filter_map(move |dy| {
+            let next = position + *offset + IVec3::Y * *dy;
+            // The mob must fit all the way from where it is to where it goes
+            let bottom = position.y.min(next.y);
+            let top = position.y.max(next.y) + height - 1;
+            let reachable = can_stand_at(world_map, next, height)
+                && is_clear(world_map, next, bottom, top)
+                && is_clear(world_map, position, position.y, top);
+            reachable.then_some(next)
+        })
+    })
+}
+
+fn distance(a: IVec3, b: IVec3) -> i32 {
+    (a - b).abs().element_sum()
+}
+
+/// Looks for a path from `start` to `goal` for a mob `height` blocks tall, with A*.\
+/// When the goal cannot be reached, the path leads to the closest position found instead.
+/// Returns the positions to walk through, without `start`, or `None` if there is nowhere to go
+pub fn find_path(
+    world_map: &impl WorldMap,
+    start: IVec3,
+    goal: IVec3,
+    height: i32,
+) -> Option<Vec<IVec3>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec3, IVec3> = HashMap::new();
+    let mut costs: HashMap<IVec3, i32> = HashMap::from([(start, 0)]);
+    let mut closest = (distance(start, goal), start);
+    open.push(Reverse((distance(start, goal), start.to_array())));
+
+    while let Some(Reverse((_, position))) = open.pop() {
+        let position = IVec3::from_array(position);
+        if position == goal || costs.len() > MAX_EXPLORED_POSITIONS {
+            break;
+        }
+        let cost = costs[&position];
+        for next in neighbours(world_map, position, height) {
+            let next_cost = cost + 1 + (next.y - position.y).abs();
+            if costs.get(&next).is_some_and(|known| *known <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            came_from.insert(next, position);
+            if distance(next, goal) < closest.0 {
+                closest = (distance(next, goal), next);
+            }
+            open.push(Reverse((next_cost + distance(next, goal), next.to_array())));
+        }
+    }
+
+    let mut position = closest.1;
+    if position == start {
+        return None;
+    }
+    let mut path = vec![position];
+    while let Some(previous) = came_from.get(&position).filter(|p| **p != start) {
+        path.push(*previous);
+        position = *previous;
+    }
+    path.reverse();
+    Some(path)
+}
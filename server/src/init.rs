@@ -7,12 +7,18 @@ use bevy_renet::renet::transport::NetcodeServerTransport;
 use bevy_renet::renet::RenetServer;
 use bevy_renet::RenetServerPlugin;
 use serde::{Deserialize, Serialize};
+use shared::world::{get_game_folder, set_registry, Registry, REGISTRY_FOLDER};
 use shared::{get_shared_renet_config, messages::PlayerId, GameFolderPaths, GameServerConfig};
 use std::fmt::Debug;
 use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, net::IpAddr};
 
-use crate::world::load_from_file::{load_world_map, load_world_seed, load_world_time};
+use crate::inventory::PlayerInventories;
+use crate::permissions::{get_permissions_path, load_permissions};
+use crate::world::data::get_world_folder;
+use crate::world::load_from_file::load_world_data;
+use crate::world::player_data::PlayerDataStorage;
+use crate::world::region::RegionStorage;
 
 use crate::dispatcher;
 use bevy_renet::renet::transport::{ServerAuthentication, ServerConfig};
@@ -25,6 +31,14 @@ pub struct ServerTime(pub u64);
 #[derive(Debug, Default, Resource)]
 pub struct ServerLobby {
     pub players: HashMap<PlayerId, String>,
+    /// Session tokens given to the authenticated players
+    pub session_tokens: HashMap<PlayerId, u128>,
+}
+
+impl ServerLobby {
+    pub fn is_valid_session(&self, player_id: PlayerId, session_token: u128) -> bool {
+        self.session_tokens.get(&player_id) == Some(&session_token)
+    }
 }
 
 #[allow(dead_code)]
@@ -37,12 +51,22 @@ pub fn acquire_socket_by_port(ip: IpAddr, port: u16) -> UdpSocket {
     UdpSocket::bind(addr).unwrap()
 }
 
-pub fn add_netcode_network(app: &mut App, socket: UdpSocket) {
+pub fn add_netcode_network(app: &mut App, socket: UdpSocket, config: &GameServerConfig) {
     app.add_plugins(NetcodeServerPlugin);
 
     let server = RenetServer::new(get_shared_renet_config());
 
     let granted_addr = &socket.local_addr().unwrap();
+    let mut public_addresses = vec![*granted_addr];
+    public_addresses.extend(config.public_addresses.iter().copied());
+
+    let authentication = match config.private_key {
+        Some(private_key) => ServerAuthentication::Secure { private_key },
+        None => {
+            warn!("Server is running without authentication, anyone can join as anybody");
+            ServerAuthentication::Unsecure
+        }
+    };
 
     let current_time: Duration = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -50,9 +74,9 @@ pub fn add_netcode_network(app: &mut App, socket: UdpSocket) {
     let server_config = ServerConfig {
         current_time,
         max_clients: 64,
-        protocol_id: shared::PROTOCOL_ID,
-        public_addresses: vec![*granted_addr],
-        authentication: ServerAuthentication::Unsecure,
+        protocol_id: shared::NETCODE_PROTOCOL_ID,
+        public_addresses,
+        authentication,
     };
 
     let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
@@ -60,6 +84,25 @@ pub fn add_netcode_network(app: &mut App, socket: UdpSocket) {
     app.insert_resource(transport);
 }
 
+/// Loads the definitions of the blocks and items, sent to the clients when they join.\
+/// The ones built into the game are kept if the files can't be read
+fn load_registry(paths: &GameFolderPaths) {
+    let registry_path = get_game_folder(Some(paths))
+        .join(paths.assets_folder_path.clone())
+        .join(REGISTRY_FOLDER);
+    match Registry::load(&registry_path) {
+        Ok(registry) => {
+            set_registry(registry);
+            info!("Registry loaded from {}", registry_path.display());
+        }
+        Err(e) => error!(
+            "Error loading registry from {}, using the built-in one: {}",
+            registry_path.display(),
+            e
+        ),
+    }
+}
+
 pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_path: String) {
     let mut app = App::new();
     app.add_plugins(
@@ -79,48 +122,54 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_path: Strin
         assets_folder_path: format!("{}/data", game_folder_path),
     });
 
-    let world_name = &config.world_name.clone();
+    load_registry(app.world().get_resource::<GameFolderPaths>().unwrap());
 
-    app.insert_resource(config);
+    let world_name = &config.world_name.clone();
 
     info!("Starting server on {}", socket.local_addr().unwrap());
 
-    add_netcode_network(&mut app, socket);
+    add_netcode_network(&mut app, socket, &config);
+
+    app.insert_resource(config);
 
     dispatcher::setup_resources_and_events(&mut app);
 
-    // Load world from files
-    let world_map = match load_world_map(world_name, &app) {
-        Ok(world) => world,
+    // Load world metadata from files, chunks are loaded lazily from region files
+    let world_data = match load_world_data(world_name, &app) {
+        Ok(data) => data,
         Err(e) => {
-            error!("Error loading world: {}. Generating a new one.", e);
+            error!("Error loading world: {}", e);
             panic!();
         }
     };
+    info!("World seed loaded successfully: {}", world_data.seed.0);
 
-    let world_seed = match load_world_seed(world_name, &app) {
-        Ok(seed) => {
-            info!("World seed loaded successfully: {}", seed.0); // Affiche la seed chargée
-            seed
-        }
-        Err(e) => {
-            error!("Error loading seed: {}. Generating a new one.", e);
-            panic!();
-        }
-    };
+    let world_path = get_world_folder(
+        app.world().get_resource::<GameFolderPaths>().unwrap(),
+        world_name,
+    );
 
-    let server_time = match load_world_time(world_name, &app) {
-        Ok(time) => time,
+    // Insert world_map and seed into ressources
+    app.insert_resource(world_data.map);
+    app.insert_resource(world_data.seed);
+    app.insert_resource(ServerTime(world_data.time));
+    let permissions = match load_permissions(&get_permissions_path(&game_folder_path)) {
+        Ok(permissions) => permissions,
         Err(e) => {
-            error!("Error loading time: {}. Defaulting to 0.", e);
-            0
+            error!("Error loading permissions: {}", e);
+            panic!();
         }
     };
-
-    // Insert world_map and seed into ressources
-    app.insert_resource(world_map);
-    app.insert_resource(world_seed);
-    app.insert_resource(ServerTime(server_time));
+    app.insert_resource(permissions);
+    app.insert_resource(PlayerInventories::default());
+    let player_data = PlayerDataStorage::new(&world_path);
+    if let Err(e) = player_data.migrate_legacy_inventories(&world_path, dispatcher::SPAWN_POSITION)
+    {
+        error!("Error migrating player inventories: {}", e);
+        panic!();
+    }
+    app.insert_resource(player_data);
+    app.insert_resource(RegionStorage::new(world_path));
 
     dispatcher::register_systems(&mut app);
 
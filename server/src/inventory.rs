@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
+use shared::messages::PlayerId;
+use shared::messages::{encode_message, InventoryLayout, InventoryUpdate, ServerToClientMessage};
+use shared::players::{
+    is_crafting_grid_slot, Inventory, INTERACTION_DISTANCE, MAX_INVENTORY_SLOTS,
+};
+use shared::world::{
+    registry, BlockId, ItemId, ItemStack, ItemType, ServerWorldMap, CRAFTING_GRID_SIZE,
+    INVENTORY_CRAFTING_GRID_SIZE,
+};
+use std::collections::HashMap;
+
+use crate::world::REACH_TOLERANCE;
+
+/// Inventories of the connected players, keyed by username
+#[derive(Resource, Default, Debug)]
+pub struct PlayerInventories {
+    pub inventories: HashMap<String, Inventory>,
+    /// Stacks held by the cursor in the inventory UI of connected players
+    pub held: HashMap<String, ItemStack>,
+}
+
+impl PlayerInventories {
+    pub fn get_or_create(&mut self, username: &str) -> &mut Inventory {
+        self.inventories.entry(username.to_string()).or_default()
+    }
+
+    /// Puts the stack held by the player back into their inventory\
+    /// Returns the items that did not fit
+    pub fn release_held_stack(&mut self, username: &str) -> Option<ItemStack> {
+        let stack = self.held.remove(username)?;
+        self.get_or_create(username).add_item_to_inventory(stack)
+    }
+
+    /// Inventory of the player as it should be persisted, with the held stack and the crafting grid put back in place
+    pub fn saved_inventory(&self, username: &str) -> Inventory {
+        let mut inventory = self.inventories.get(username).cloned().unwrap_or_default();
+        inventory.release_crafting_grid();
+        if let Some(stack) = self.held.get(username) {
+            inventory.add_item_to_inventory(*stack);
+        }
+        inventory
+    }
+
+    /// Forgets the inventory of a leaving player, returning it as it should be persisted,
+    /// along with the items that did not fit back into it
+    pub fn remove(&mut self, username: &str) -> (Inventory, Vec<ItemStack>) {
+        let mut inventory = self.inventories.remove(username).unwrap_or_default();
+        let mut leftovers = inventory.release_crafting_grid();
+        if let Some(stack) = self.held.remove(username) {
+            leftovers.extend(inventory.add_item_to_inventory(stack));
+        }
+        (inventory, leftovers)
+    }
+}
+
+pub fn send_inventory(server: &mut RenetServer, client_id: ClientId, inventory: &Inventory) {
+    let payload = encode_message(&ServerToClientMessage::PlayerInventory(inventory.clone()));
+    server.send_message(client_id, DefaultChannel::ReliableUnordered, payload);
+}
+
+/// Sends the slots of `inventory` that changed since `previous`, if any
+pub fn send_inventory_diff(
+    server: &mut RenetServer,
+    client_id: ClientId,
+    inventory: &Inventory,
+    previous: &Inventory,
+) {
+    let slots = inventory.diff(previous);
+    if slots.is_empty() {
+        return;
+    }
+    send_inventory_slots(server, client_id, slots);
+}
+
+pub fn send_inventory_slots(
+    server: &mut RenetServer,
+    client_id: ClientId,
+    slots: HashMap<u32, Option<ItemStack>>,
+) {
+    let payload = encode_message(&ServerToClientMessage::InventoryUpdate(InventoryUpdate {
+        slots,
+    }));
+    server.send_message(client_id, DefaultChannel::ReliableUnordered, payload);
+}
+
+/// Counts the items of each kind in the given stacks
+fn count_items<'a>(stacks: impl Iterator<Item = &'a ItemStack>) -> Vec<(ItemId, ItemType, u32)> {
+    let mut counts: Vec<(ItemId, ItemType, u32)> = Vec::new();
+    for stack in stacks {
+        match counts
+            .iter_mut()
+            .find(|(id, item_type, _)| *id == stack.item_id && *item_type == stack.item_type)
+        {
+            Some((_, _, nb)) => *nb += stack.nb,
+            None => counts.push((stack.item_id, stack.item_type, stack.nb)),
+        }
+    }
+    counts
+}
+
+/// Applies a new inventory arrangement sent by a client.\
+/// The arrangement is only accepted if it contains exactly the same items as the
+/// server-side inventory, so items can be moved around but not created.
+/// Returns `false` if the arrangement was rejected
+pub fn apply_inventory_layout(
+    inventories: &mut PlayerInventories,
+    username: &str,
+    layout: InventoryLayout,
+) -> bool {
+    let valid_slots = layout.slots.iter().all(|(slot, stack)| {
+        (*slot < MAX_INVENTORY_SLOTS || is_crafting_grid_slot(*slot))
+            && stack.nb > 0
+            && stack.nb <= stack.item_id.get_max_stack()
+    });
+    if !valid_slots {
+        return false;
+    }
+
+    let held = inventories.held.get(username).copied();
+    let inventory = inventories.get_or_create(username);
+
+    let current = count_items(inventory.inner.values().chain(held.iter()));
+    let new = count_items(layout.slots.values().chain(layout.held.iter()));
+    if current.len() != new.len() || current.iter().any(|count| !new.contains(count)) {
+        return false;
+    }
+
+    inventory.inner = layout.slots;
+    match layout.held {
+        Some(stack) => inventories.held.insert(username.to_string(), stack),
+        None => inventories.held.remove(username),
+    };
+    true
+}
+
+/// Crafts the recipe laid out in the crafting grid of the player, the crafted items go into the inventory.\
+/// Without crafting `table`, only the smaller grid of the inventory can be used.\
+/// Returns the reason of the rejection, if any
+pub fn craft_item(
+    inventory: &mut Inventory,
+    world_map: &ServerWorldMap,
+    player_id: PlayerId,
+    table: Option<IVec3>,
+) -> Result<(), &'static str> {
+    let size = match table {
+        None => INVENTORY_CRAFTING_GRID_SIZE,
+        Some(position) => {
+            if world_map
+                .get_block_by_coordinates(&position)
+                .map(|block| block.id)
+                != Some(BlockId::CRAFTING_TABLE)
+            {
+                return Err("no crafting table");
+            }
+            let Some(player_position) = world_map.player_positions.get(&player_id) else {
+                return Err("unknown player");
+            };
+            let table_center = position.as_vec3() + Vec3::splat(0.5);
+            if table_center.distance(*player_position) > INTERACTION_DISTANCE + REACH_TOLERANCE {
+                return Err("crafting table out of reach");
+            }
+            CRAFTING_GRID_SIZE
+        }
+    };
+
+    let grid = inventory
+        .crafting_grid(size)
+        .ok_or("items outside of the crafting grid")?;
+    let recipe = registry()
+        .find_recipe(&grid, size)
+        .cloned()
+        .ok_or("no matching recipe")?;
+    let stack = recipe.result_stack();
+    if !inventory.has_room_for(&stack) {
+        return Err("inventory full");
+    }
+
+    inventory.consume_crafting_grid();
+    inventory.add_item_to_inventory(stack);
+    Ok(())
+}
@@ -1,6 +1,11 @@
 mod chat;
+mod commands;
+mod disconnect;
 mod dispatcher;
+mod entities;
 mod init;
+mod inventory;
+mod permissions;
 mod player;
 pub mod time;
 mod world;
@@ -1,12 +1,22 @@
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 
 use crate::init::acquire_socket_by_port;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use shared::auth::{
+    connect_token_file_name, generate_connect_token, load_or_create_private_key,
+    CONNECT_TOKENS_FOLDER, PRIVATE_KEY_FILE,
+};
 use shared::GameServerConfig;
 
 mod chat;
+mod commands;
+mod disconnect;
 mod dispatcher;
+mod entities;
 mod init;
+mod inventory;
+mod permissions;
 mod player;
 mod time;
 mod world;
@@ -14,6 +24,9 @@ mod world;
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, long, default_value_t = 0)]
     port: u16,
 
@@ -22,10 +35,70 @@ struct Args {
 
     #[arg(short, long, default_value = "../")]
     game_folder_path: String,
+
+    /// File holding the private key of the server, created if missing.
+    /// Defaults to `server_key.bin` in the game folder
+    #[arg(long)]
+    private_key_file: Option<PathBuf>,
+
+    /// Address clients use to reach the server, can be repeated
+    #[arg(long)]
+    public_address: Vec<SocketAddr>,
+
+    /// Lets clients join without a connect token
+    #[arg(long)]
+    unsecure: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Issues a connect token allowing a player to join the server
+    Token {
+        #[arg(short, long)]
+        username: String,
+
+        /// Address clients use to reach the server, can be repeated
+        #[arg(short, long, required = true)]
+        address: Vec<SocketAddr>,
+
+        /// Number of days the token stays valid
+        #[arg(long, default_value_t = 30)]
+        expire_days: u64,
+
+        /// Where to write the token. Defaults to `<username>.token`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 fn main() {
     let args = Args::parse();
+
+    let private_key_file = args
+        .private_key_file
+        .clone()
+        .unwrap_or_else(|| Path::new(&args.game_folder_path).join(PRIVATE_KEY_FILE));
+
+    if let Some(Command::Token {
+        username,
+        address,
+        expire_days,
+        output,
+    }) = args.command
+    {
+        issue_connect_token(&private_key_file, &username, address, expire_days, output);
+        return;
+    }
+
+    let private_key = if args.unsecure {
+        None
+    } else {
+        match load_or_create_private_key(&private_key_file) {
+            Ok(key) => Some(key),
+            Err(e) => panic!("Failed to load private key: {}", e),
+        }
+    };
+
     let socket = acquire_socket_by_port(std::net::IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), args.port);
 
     let game_folder_path = args.game_folder_path.clone();
@@ -35,7 +108,52 @@ fn main() {
         GameServerConfig {
             world_name: args.world,
             is_solo: false,
+            private_key,
+            public_addresses: args.public_address,
         },
         game_folder_path,
     );
 }
+
+fn issue_connect_token(
+    private_key_file: &Path,
+    username: &str,
+    addresses: Vec<SocketAddr>,
+    expire_days: u64,
+    output: Option<PathBuf>,
+) {
+    let private_key = match load_or_create_private_key(private_key_file) {
+        Ok(key) => key,
+        Err(e) => panic!("Failed to load private key: {}", e),
+    };
+
+    let client_id = rand::random();
+    let token = match generate_connect_token(
+        &private_key,
+        username,
+        client_id,
+        addresses.clone(),
+        expire_days * 24 * 60 * 60,
+    ) {
+        Ok(token) => token,
+        Err(e) => panic!("Failed to generate connect token: {}", e),
+    };
+
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.token", username)));
+    if let Err(e) = token.save(&output) {
+        panic!("Failed to write connect token: {}", e);
+    }
+
+    println!(
+        "Connect token for {} written to {}",
+        username,
+        output.display()
+    );
+    for address in addresses {
+        println!(
+            "Copy it to <game folder>/{}/{} on the player's computer",
+            CONNECT_TOKENS_FOLDER,
+            connect_token_file_name(&address)
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the file holding operators and bans, inside the game folder
+pub const PERMISSIONS_FILE: &str = "permissions.ron";
+
+/// Each level grants the commands of the levels below it
+pub type PermissionLevel = u8;
+
+pub const LEVEL_PLAYER: PermissionLevel = 0;
+/// Can kick and teleport players
+pub const LEVEL_MODERATOR: PermissionLevel = 1;
+/// Can change the time, give items and see the seed
+pub const LEVEL_GAME_MASTER: PermissionLevel = 2;
+/// Can save and stop the server, ban players and manage operators
+pub const LEVEL_ADMIN: PermissionLevel = 3;
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+pub struct ServerPermissions {
+    /// Permission level of each operator, by username
+    pub ops: HashMap<String, PermissionLevel>,
+    pub banned: HashSet<String>,
+}
+
+impl ServerPermissions {
+    pub fn level_of(&self, username: &str) -> PermissionLevel {
+        self.ops.get(username).copied().unwrap_or(LEVEL_PLAYER)
+    }
+
+    pub fn set_level(&mut self, username: &str, level: PermissionLevel) {
+        if level == LEVEL_PLAYER {
+            self.ops.remove(username);
+        } else {
+            self.ops.insert(username.to_string(), level);
+        }
+    }
+}
+
+pub fn get_permissions_path(game_folder_path: &str) -> PathBuf {
+    Path::new(game_folder_path).join(PERMISSIONS_FILE)
+}
+
+pub fn load_permissions(path: &Path) -> Result<ServerPermissions, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Ok(ServerPermissions::default());
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(ron::de::from_str(&contents)?)
+}
+
+pub fn save_permissions(
+    permissions: &ServerPermissions,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pretty_config = PrettyConfig::new()
+        .with_depth_limit(3)
+        .with_separate_tuple_members(true)
+        .with_enumerate_arrays(true);
+
+    let serialized = ron::ser::to_string_pretty(permissions, pretty_config)?;
+    fs::write(path, serialized)?;
+    Ok(())
+}
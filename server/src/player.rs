@@ -1,10 +1,157 @@
-use crate::init::TickCounter;
 use bevy::prelude::*;
-use bevy_ecs::prelude::Res;
-use shared::messages::PlayerInputs;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
+use shared::messages::{
+    encode_message, NetworkPlayerInput, PlayerId, PlayerInputs, PlayerStateUpdate,
+    ServerToClientMessage,
+};
+use shared::players::{simulate_player_movement, Inventory, PlayerMovementState, SIMULATION_DELTA};
+use shared::world::ServerWorldMap;
+use std::collections::{HashMap, VecDeque};
 
-pub fn handle_player_inputs(player_inputs: PlayerInputs, ticker: &Res<TickCounter>) {
-    if ticker.tick % 60 == 0 {
-        trace!("Received inputs: {:?}", player_inputs);
+use crate::world::player_data::PlayerData;
+
+/// Inputs received beyond this amount are dropped until the queue is processed
+const MAX_PENDING_INPUTS: usize = 120;
+
+/// Maximum simulated time a player can accumulate, in seconds.
+/// Prevents a client from sending inputs faster than real time to move faster
+const MAX_TIME_BUDGET: f32 = 0.25;
+
+pub struct PlayerMovement {
+    pub state: PlayerMovementState,
+    pub pending_inputs: VecDeque<PlayerInputs>,
+    pub last_input_tick: u64,
+    /// Direction the player looked at in their last inputs
+    pub look_direction: Vec3,
+    time_budget: f32,
+}
+
+impl PlayerMovement {
+    pub fn new(data: &PlayerData) -> Self {
+        let mut state = PlayerMovementState::new(data.position);
+        state.is_flying = data.is_flying;
+        state.spawn_point = data.spawn_point;
+        Self {
+            state,
+            pending_inputs: VecDeque::new(),
+            last_input_tick: 0,
+            look_direction: data.look_direction,
+            time_budget: 0.0,
+        }
+    }
+
+    /// Data to persist for this player, along with their inventory
+    pub fn to_data(&self, inventory: Inventory) -> PlayerData {
+        PlayerData {
+            position: self.state.position,
+            look_direction: self.look_direction,
+            inventory,
+            is_flying: self.state.is_flying,
+            spawn_point: self.state.spawn_point,
+        }
+    }
+}
+
+/// Authoritative movement state of every connected player
+#[derive(Default, Resource)]
+pub struct PlayerMovements {
+    pub players: HashMap<PlayerId, PlayerMovement>,
+}
+
+pub fn handle_player_inputs(
+    player_id: PlayerId,
+    mut player_inputs: PlayerInputs,
+    can_fly: bool,
+    movements: &mut ResMut<PlayerMovements>,
+) {
+    let Some(movement) = movements.players.get_mut(&player_id) else {
+        debug!("Received inputs from unregistered player {}", player_id);
+        return;
+    };
+
+    let last_queued_tick = movement
+        .pending_inputs
+        .back()
+        .map_or(movement.last_input_tick, |inputs| inputs.tick);
+
+    if player_inputs.tick <= last_queued_tick || movement.pending_inputs.len() >= MAX_PENDING_INPUTS
+    {
+        trace!(
+            "Dropped inputs of player {}: {:?}",
+            player_id,
+            player_inputs
+        );
+        return;
     }
+
+    if !can_fly {
+        player_inputs
+            .actions
+            .retain(|action| *action != NetworkPlayerInput::ToggleFlyMode);
+    }
+    movement.pending_inputs.push_back(player_inputs);
+}
+
+/// Steps the movement of every player with the inputs they sent,
+/// and sends the resulting state back to them
+pub fn simulate_players_movement(
+    mut server: ResMut<RenetServer>,
+    mut movements: ResMut<PlayerMovements>,
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<Time>,
+) {
+    for (player_id, movement) in movements.players.iter_mut() {
+        movement.time_budget = (movement.time_budget + time.delta_seconds()).min(MAX_TIME_BUDGET);
+
+        let mut processed = false;
+        while movement.time_budget >= SIMULATION_DELTA {
+            let Some(inputs) = movement.pending_inputs.pop_front() else {
+                break;
+            };
+            simulate_player_movement(&mut movement.state, world_map.as_ref(), &inputs);
+            movement.last_input_tick = inputs.tick;
+            movement.look_direction = inputs.direction;
+            movement.time_budget -= SIMULATION_DELTA;
+            processed = true;
+        }
+
+        if !processed {
+            continue;
+        }
+
+        world_map
+            .player_positions
+            .insert(*player_id, movement.state.position);
+
+        send_player_state(&mut server, *player_id, movement);
+    }
+}
+
+/// Sends the authoritative movement state of a player to them
+pub fn send_player_state(server: &mut RenetServer, player_id: PlayerId, movement: &PlayerMovement) {
+    let payload = encode_message(&ServerToClientMessage::PlayerStateUpdate(
+        PlayerStateUpdate {
+            last_input_tick: movement.last_input_tick,
+            state: movement.state,
+        },
+    ));
+    server.send_message(
+        ClientId::from_raw(player_id),
+        DefaultChannel::ReliableUnordered,
+        payload,
+    );
+}
+
+/// Moves a player, as if they had been there all along
+pub fn teleport_player(
+    server: &mut RenetServer,
+    world_map: &mut ServerWorldMap,
+    player_id: PlayerId,
+    movement: &mut PlayerMovement,
+    position: Vec3,
+) {
+    movement.state.position = position;
+    movement.state.vertical_velocity = 0.0;
+    world_map.player_positions.insert(player_id, position);
+    send_player_state(server, player_id, movement);
 }
@@ -1,85 +1,189 @@
 use crate::init::ServerTime;
 use crate::init::TickCounter;
-use crate::world::generation::generate_chunk;
+use crate::world::loading::ChunkGenerationQueue;
 use crate::world::utils::format_bytes;
 use bevy::math::IVec3;
 use bevy::prelude::*;
 use bevy_ecs::system::ResMut;
 use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
-use bincode::Options;
-use shared::messages::{ServerToClientMessage, WorldUpdate};
-use shared::world::{chunk_in_radius, ServerChunk, ServerWorldMap};
-use std::collections::HashMap;
+use shared::messages::{
+    encode_message, BlockChanges, PlayerId, ServerToClientMessage, WorldUpdate,
+};
+use shared::world::{
+    chunk_in_radius, global_block_to_chunk_pos, BlockData, ServerChunk, ServerWorldMap,
+};
+use std::collections::{HashMap, HashSet};
 
-use shared::world::data::WorldSeed;
+/// Largest render distance a client can ask for, in chunks
+pub const MAX_RENDER_DISTANCE: u32 = 32;
+/// Largest vertical render distance a client can ask for, in chunks
+pub const MAX_VERTICAL_RENDER_DISTANCE: u32 = 16;
 
-#[derive(Event, Debug)]
-pub struct WorldUpdateRequestEvent {
-    pub client: ClientId,
-    pub chunks: Vec<IVec3>,
+/// Chunks held by a client, around its player
+#[derive(Debug)]
+pub struct ChunkView {
     pub render_distance: u32,
-    pub player_chunk_position: IVec3,
+    pub vertical_render_distance: u32,
+    /// Chunk the player was in when the view was last updated. `None` forces an update
+    pub center: Option<IVec3>,
+    pub loaded: HashSet<IVec3>,
+    /// Chunks around the player the client does not have yet, sent as soon as they are ready
+    pub missing: HashSet<IVec3>,
 }
 
+impl ChunkView {
+    pub fn new(render_distance: u32, vertical_render_distance: u32) -> Self {
+        let mut view = Self {
+            render_distance: 0,
+            vertical_render_distance: 0,
+            center: None,
+            loaded: HashSet::new(),
+            missing: HashSet::new(),
+        };
+        view.set_render_distance(render_distance, vertical_render_distance);
+        view
+    }
+
+    pub fn set_render_distance(&mut self, render_distance: u32, vertical_render_distance: u32) {
+        self.render_distance = render_distance.clamp(1, MAX_RENDER_DISTANCE);
+        self.vertical_render_distance =
+            vertical_render_distance.clamp(1, MAX_VERTICAL_RENDER_DISTANCE);
+        self.center = None;
+    }
+
+    fn in_view(&self, center: &IVec3, chunk: &IVec3) -> bool {
+        chunk_in_radius(
+            center,
+            chunk,
+            self.render_distance as i32,
+            self.vertical_render_distance as i32,
+        )
+    }
+
+    fn chunks_around(&self, center: IVec3) -> HashSet<IVec3> {
+        let r = self.render_distance as i32;
+        let v = self.vertical_render_distance as i32;
+        let mut chunks = HashSet::new();
+        for x in -r..=r {
+            for z in -r..=r {
+                for y in -v..=v {
+                    chunks.insert(center + IVec3::new(x, y, z));
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// Chunks each client holds. Clients get the chunks around their player as it moves,
+/// and are told about the changes of the blocks in these chunks
+#[derive(Resource, Default, Debug)]
+pub struct ClientChunkViews {
+    pub views: HashMap<PlayerId, ChunkView>,
+}
+
+/// Chunk containing a position in the world
+pub(crate) fn chunk_of(position: &Vec3) -> IVec3 {
+    global_block_to_chunk_pos(&position.floor().as_ivec3())
+}
+
+/// Follows the players as they move: tells the clients which chunks left their view,
+/// and sends the chunks entering it as soon as they are loaded
 pub fn send_world_update(
     mut server: ResMut<RenetServer>,
     ticker: Res<TickCounter>,
-    seed: Res<WorldSeed>,
-    mut world_map: ResMut<ServerWorldMap>,
-    mut ev_update: EventReader<WorldUpdateRequestEvent>,
+    world_map: Res<ServerWorldMap>,
+    mut views: ResMut<ClientChunkViews>,
+    mut generation: ResMut<ChunkGenerationQueue>,
 ) {
-    let mut chunks_to_update_count = 0;
-    for event in ev_update.read() {
-        let payload = bincode::options()
-            .serialize(&ServerToClientMessage::WorldUpdate(WorldUpdate {
-                tick: ticker.tick,
-                player_positions: world_map.player_positions.clone(),
-                new_map: {
-                    let mut map: HashMap<IVec3, ServerChunk> = HashMap::new();
-                    for c in event.chunks.iter() {
-                        if chunk_in_radius(
-                            &event.player_chunk_position,
-                            c,
-                            event.render_distance as i32,
-                        ) {
-                            let chunk = world_map.map.get(c);
-
-                            // If chunk already exists, transmit it to client
-                            if let Some(chunk) = chunk {
-                                if chunk.map.is_empty() {
-                                    continue;
-                                }
-
-                                chunks_to_update_count += 1;
-                                map.insert(*c, chunk.clone());
-                            } else {
-                                // If chunk does not exists, generate it before transmitting it
-                                let chunk = generate_chunk(*c, seed.0);
-
-                                // If chunk is empty, do not create it to prevent unnecessary data transmission
-                                if chunk.map.is_empty() {
-                                    continue;
-                                }
-
-                                chunks_to_update_count += 1;
-                                map.insert(*c, chunk.clone());
-                                world_map.map.insert(*c, chunk);
-                            }
-                        }
-                    }
-                    trace!("Update event yippeee :D    len={}", map.len());
-                    map
-                },
-                time: world_map.time,
-            }))
-            .unwrap();
+    let mut views_moved = false;
+    for (player_id, view) in views.views.iter_mut() {
+        let Some(position) = world_map.player_positions.get(player_id) else {
+            continue;
+        };
+        let center = chunk_of(position);
+        if view.center == Some(center) {
+            continue;
+        }
+        view.center = Some(center);
+        views_moved = true;
+
+        let unloaded: Vec<IVec3> = view
+            .loaded
+            .iter()
+            .filter(|chunk| !view.in_view(&center, chunk))
+            .copied()
+            .collect();
+        if !unloaded.is_empty() {
+            debug!("Unloading {} chunks for {}", unloaded.len(), player_id);
+            for chunk in unloaded.iter() {
+                view.loaded.remove(chunk);
+            }
+            let payload = encode_message(&ServerToClientMessage::UnloadChunks(unloaded));
+            server.send_message(
+                ClientId::from_raw(*player_id),
+                DefaultChannel::ReliableOrdered,
+                payload,
+            );
+        }
+
+        view.missing = view
+            .chunks_around(center)
+            .difference(&view.loaded)
+            .copied()
+            .collect();
+    }
+
+    if views_moved {
+        generation.prioritize(&views, &world_map);
+    }
+
+    for (player_id, view) in views.views.iter_mut() {
+        let ready: Vec<IVec3> = view
+            .missing
+            .iter()
+            .filter(|chunk| generation.is_ready(&world_map, chunk))
+            .copied()
+            .collect();
+        if ready.is_empty() {
+            continue;
+        }
+
+        let mut new_map: HashMap<IVec3, ServerChunk> = HashMap::new();
+        for chunk_pos in ready {
+            view.missing.remove(&chunk_pos);
+            view.loaded.insert(chunk_pos);
+            // Empty chunks are not sent, the client knows it has nothing there
+            if let Some(chunk) = world_map.map.get(&chunk_pos) {
+                if !chunk.map.is_empty() {
+                    new_map.insert(chunk_pos, chunk.clone());
+                }
+            }
+        }
+
+        if new_map.is_empty() {
+            continue;
+        }
+        let chunks_count = new_map.len();
+        let payload = encode_message(&ServerToClientMessage::WorldUpdate(WorldUpdate {
+            tick: ticker.tick,
+            player_positions: HashMap::new(),
+            new_map,
+            time: world_map.time,
+        }));
 
         debug!(
-            "Broadcasting world state, number of chunks = {}, payload size: {}",
-            chunks_to_update_count,
+            "Sending chunks to {}, number of chunks = {}, payload size: {}",
+            player_id,
+            chunks_count,
             format_bytes(payload.len() as u64)
         );
-        server.send_message(event.client, DefaultChannel::ReliableUnordered, payload);
+        // Sent on the same channel as block changes, so that they apply on top of the chunk
+        server.send_message(
+            ClientId::from_raw(*player_id),
+            DefaultChannel::ReliableOrdered,
+            payload,
+        );
     }
 }
 
@@ -87,6 +191,7 @@ pub fn broadcast_world_state(
     mut server: ResMut<RenetServer>,
     ticker: Res<TickCounter>,
     mut world_map: ResMut<ServerWorldMap>,
+    views: Res<ClientChunkViews>,
     time: Res<ServerTime>,
 ) {
     if ticker.tick % 10 != 0 {
@@ -97,29 +202,62 @@ pub fn broadcast_world_state(
     world_map.time = time.0;
 
     trace!("Broadcast world update");
-    let payload = bincode::options()
-        .serialize(&ServerToClientMessage::WorldUpdate(to_network(
-            &mut world_map,
-            ticker.tick,
-        )))
-        .unwrap();
-    server.broadcast_message(DefaultChannel::ReliableUnordered, payload);
+    for (player_id, view) in views.views.iter() {
+        // Players only see the other players standing in the chunks they hold
+        let player_positions = world_map
+            .player_positions
+            .iter()
+            .filter(|(id, position)| *id == player_id || view.loaded.contains(&chunk_of(position)))
+            .map(|(id, position)| (*id, *position))
+            .collect();
+
+        let payload = encode_message(&ServerToClientMessage::WorldUpdate(WorldUpdate {
+            tick: ticker.tick,
+            player_positions,
+            new_map: HashMap::new(),
+            time: world_map.time,
+        }));
+        server.send_message(
+            ClientId::from_raw(*player_id),
+            DefaultChannel::ReliableUnordered,
+            payload,
+        );
+    }
 }
 
-fn to_network(world_map: &mut ServerWorldMap, tick: u64) -> WorldUpdate {
-    WorldUpdate {
-        tick,
-        player_positions: world_map.player_positions.clone(),
-        new_map: {
-            let mut m: HashMap<IVec3, ServerChunk> = HashMap::new();
-            // Only send chunks that must be updated
-            for v in world_map.chunks_to_update.iter() {
-                m.insert(*v, world_map.map.get(v).unwrap().clone());
-            }
-            // Chunks are up do date, clear the vector
-            world_map.chunks_to_update.clear();
-            m
-        },
-        time: world_map.time,
+/// Sends the changed blocks to the clients holding their chunk
+pub fn broadcast_block_changes(
+    mut server: ResMut<RenetServer>,
+    mut world_map: ResMut<ServerWorldMap>,
+    views: Res<ClientChunkViews>,
+) {
+    if world_map.block_changes.is_empty() {
+        return;
+    }
+    let changes: Vec<(IVec3, Option<BlockData>)> = world_map.block_changes.drain().collect();
+
+    for (player_id, view) in views.views.iter() {
+        let visible_changes: Vec<_> = changes
+            .iter()
+            .filter(|(position, _)| view.loaded.contains(&global_block_to_chunk_pos(position)))
+            .copied()
+            .collect();
+        if visible_changes.is_empty() {
+            continue;
+        }
+
+        trace!(
+            "Sending {} block changes to {}",
+            visible_changes.len(),
+            player_id
+        );
+        let payload = encode_message(&ServerToClientMessage::BlockChanges(BlockChanges {
+            changes: visible_changes,
+        }));
+        server.send_message(
+            ClientId::from_raw(*player_id),
+            DefaultChannel::ReliableOrdered,
+            payload,
+        );
     }
 }
@@ -1,14 +1,23 @@
-use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use shared::world::ServerChunk;
-use std::collections::HashMap;
+use shared::world::{get_game_folder, WorldSeed};
+use shared::GameFolderPaths;
+use std::path::PathBuf;
 
 pub const SAVE_PATH: &str = "saves/";
 
+/// Name of the file holding the world metadata, inside the world folder
+pub const WORLD_METADATA_FILE: &str = "world.ron";
+
+/// Small, human-readable part of a world save. Chunks are stored in region files
 #[derive(Serialize, Deserialize)]
-pub struct Save {
-    pub map: HashMap<IVec3, ServerChunk>,
-    // pub player_pos: Vec3,
-    // pub inventory: HashMap<RegistryId, Item>,
-    // pub id_to_item: HashMap<RegistryId, String>,
+pub struct WorldMetadata {
+    pub seed: WorldSeed,
+    pub time: u64,
+}
+
+/// Folder containing every file of the given world
+pub fn get_world_folder(paths: &GameFolderPaths, world_name: &str) -> PathBuf {
+    get_game_folder(Some(paths))
+        .join(SAVE_PATH)
+        .join(world_name)
 }
@@ -1,38 +1,62 @@
 use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
-use shared::{world::*, CHUNK_SIZE};
-use std::collections::HashMap;
-
-fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId, leaves: BlockId) {
-    // create trunk
-    let trunk_height = 3 + rand::random::<u8>() % 3; // random height between 3 and 5
-    for dy in 0..trunk_height {
-        chunk.map.insert(
-            IVec3::new(x, y + dy as i32, z),
-            BlockData::new(trunk, false, BlockDirection::Front),
-        );
-    }
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use shared::{world::*, CHUNK_SIZE, WORLD_MIN_Y};
 
-    // place the leaves
-    let leaf_start_y = y + trunk_height as i32 - 1;
-    for offset_x in -1..=1 {
-        for offset_z in -1..=1 {
-            if (offset_x != 0 || offset_z != 0) && (offset_x == 0 || offset_z == 0) {
-                chunk.map.insert(
-                    IVec3::new(x + offset_x, leaf_start_y, z + offset_z),
-                    BlockData::new(leaves, false, BlockDirection::Front),
-                );
-            }
-        }
+use super::structures::place_structures;
+use super::underground::{carve_caves, place_ores, CaveNoise};
+
+/// Random parts of the generation. Each one draws from its own sequence,
+/// so that changing one does not alter the others
+#[derive(Clone, Copy)]
+pub(super) enum Feature {
+    Flora = 1,
+    Trees = 2,
+    Boulders = 3,
+    Ores = 4,
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Random number generator of a feature in a chunk.\
+/// It only depends on the seed, the chunk and the feature, so a chunk is the same every time it is generated
+pub(super) fn feature_rng(seed: u32, chunk_pos: IVec3, feature: Feature) -> ChaCha8Rng {
+    let mut state = seed as u64;
+    for value in [chunk_pos.x, chunk_pos.y, chunk_pos.z, feature as i32] {
+        state = splitmix64(state ^ value as u32 as u64);
     }
-    // add one leaf block at the top of the trunk
-    chunk.map.insert(
-        IVec3::new(x, leaf_start_y + 1, z),
-        BlockData::new(leaves, false, BlockDirection::Front),
-    );
+    ChaCha8Rng::seed_from_u64(state)
 }
 
-pub fn determine_biome(temperature: f64, humidity: f64) -> BiomeType {
+/// Height under which every empty block is filled with water
+pub const SEA_LEVEL: i32 = 61;
+/// Blocks above the sea level the beaches go up to
+pub(super) const BEACH_HEIGHT: i32 = 1;
+
+/// Continentalness under which the land sinks into the ocean
+const OCEAN_THRESHOLD: f64 = 0.35;
+/// Rivers flow where the river noise is this close to its middle value
+const RIVER_WIDTH: f64 = 0.015;
+
+pub fn determine_biome(
+    temperature: f64,
+    humidity: f64,
+    continentalness: f64,
+    river: f64,
+) -> BiomeType {
+    if continentalness < OCEAN_THRESHOLD {
+        return BiomeType::Ocean;
+    }
+    if (river - 0.5).abs() < RIVER_WIDTH {
+        return BiomeType::River;
+    }
+
     if temperature > 0.7 {
         if humidity > 0.5 {
             BiomeType::Forest
@@ -56,222 +80,217 @@ pub fn determine_biome(temperature: f64, humidity: f64) -> BiomeType {
     }
 }
 
-fn interpolated_height(
-    x: i32,
-    z: i32,
-    biome_scale: f64,
-    perlin: &Perlin,
-    temp_perlin: &Perlin,
-    humidity_perlin: &Perlin,
-    scale: f64,
-) -> i32 {
-    // get the properties of the main biome at (x, z)
-    let temperature =
-        (temp_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-    let humidity =
-        (humidity_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-    let biome_type = determine_biome(temperature, humidity);
-    let biome = get_biome_data(biome_type);
-
-    // initialize weighted values
-    let mut weighted_base_height = biome.base_height as f64;
-    let mut weighted_variation = biome.height_variation as f64;
-    let mut total_weight = 1.0;
-
-    // loop through neighboring blocks to get influences
-    for &offset_x in &[-4, 0, 4] {
-        for &offset_z in &[-4, 0, 4] {
-            if offset_x == 0 && offset_z == 0 {
-                continue; // ignore the central position
-            }
+/// Noises the terrain is made of, shared by the terrain and the structures growing on it
+pub(crate) struct TerrainNoise {
+    perlin: Perlin,
+    temp_perlin: Perlin,
+    humidity_perlin: Perlin,
+    /// Low values are oceans
+    continental_perlin: Perlin,
+    /// Rivers follow the middle values
+    river_perlin: Perlin,
+}
+
+impl TerrainNoise {
+    const SCALE: f64 = 0.1;
+    const BIOME_SCALE: f64 = 0.02;
+    const CONTINENTAL_SCALE: f64 = 0.004;
+    const RIVER_SCALE: f64 = 0.004;
 
-            let neighbor_x = x + offset_x;
-            let neighbor_z = z + offset_z;
-
-            // calculate the temperature and humidity of the neighboring block
-            let neighbor_temp = (temp_perlin.get([
-                neighbor_x as f64 * biome_scale,
-                neighbor_z as f64 * biome_scale,
-            ]) + 1.0)
-                / 2.0;
-            let neighbor_humidity = (humidity_perlin.get([
-                neighbor_x as f64 * biome_scale,
-                neighbor_z as f64 * biome_scale,
-            ]) + 1.0)
-                / 2.0;
-
-            // determine the biome of the neighboring block
-            let neighbor_biome_type = determine_biome(neighbor_temp, neighbor_humidity);
-            let neighbor_biome = get_biome_data(neighbor_biome_type);
-
-            // weight by distance (the farther a neighbor is, the less influence it has)
-            let distance = ((offset_x.pow(2) + offset_z.pow(2)) as f64).sqrt();
-            let weight = 1.0 / (distance + 1.0); // distance +1 to avoid division by zero
-
-            // update weighted values
-            weighted_base_height += neighbor_biome.base_height as f64 * weight;
-            weighted_variation += neighbor_biome.height_variation as f64 * weight;
-            total_weight += weight;
+    pub fn new(seed: u32) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            temp_perlin: Perlin::new(seed.wrapping_add(1)),
+            humidity_perlin: Perlin::new(seed.wrapping_add(2)),
+            continental_perlin: Perlin::new(seed.wrapping_add(6)),
+            river_perlin: Perlin::new(seed.wrapping_add(7)),
         }
     }
 
-    // normalize weighted values
-    weighted_base_height /= total_weight;
-    weighted_variation /= total_weight;
+    pub fn biome_at(&self, x: i32, z: i32) -> BiomeType {
+        let position = [x as f64 * Self::BIOME_SCALE, z as f64 * Self::BIOME_SCALE];
+        // calculate temperature and humidity
+        let temperature = (self.temp_perlin.get(position) + 1.0) / 2.0;
+        let humidity = (self.humidity_perlin.get(position) + 1.0) / 2.0;
+        // oceans and rivers are larger than the other biomes
+        let continentalness = (self.continental_perlin.get([
+            x as f64 * Self::CONTINENTAL_SCALE,
+            z as f64 * Self::CONTINENTAL_SCALE,
+        ]) + 1.0)
+            / 2.0;
+        let river = (self
+            .river_perlin
+            .get([x as f64 * Self::RIVER_SCALE, z as f64 * Self::RIVER_SCALE])
+            + 1.0)
+            / 2.0;
+        // get biome regarding the values
+        determine_biome(temperature, humidity, continentalness, river)
+    }
+
+    /// Height of the surface block of a column
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        // get the properties of the main biome at (x, z)
+        let biome = get_biome_data(self.biome_at(x, z));
+
+        // initialize weighted values
+        let mut weighted_base_height = biome.base_height as f64;
+        let mut weighted_variation = biome.height_variation as f64;
+        let mut total_weight = 1.0;
 
-    // final calculation of height with perlin noise
-    let terrain_noise = perlin.get([x as f64 * scale, z as f64 * scale]);
-    let interpolated_height = weighted_base_height + (weighted_variation * terrain_noise);
+        // loop through neighboring blocks to get influences
+        for &offset_x in &[-4, 0, 4] {
+            for &offset_z in &[-4, 0, 4] {
+                if offset_x == 0 && offset_z == 0 {
+                    continue; // ignore the central position
+                }
+
+                // determine the biome of the neighboring block
+                let neighbor_biome = get_biome_data(self.biome_at(x + offset_x, z + offset_z));
+
+                // weight by distance (the farther a neighbor is, the less influence it has)
+                let distance = ((offset_x.pow(2) + offset_z.pow(2)) as f64).sqrt();
+                let weight = 1.0 / (distance + 1.0); // distance +1 to avoid division by zero
+
+                // update weighted values
+                weighted_base_height += neighbor_biome.base_height as f64 * weight;
+                weighted_variation += neighbor_biome.height_variation as f64 * weight;
+                total_weight += weight;
+            }
+        }
 
-    interpolated_height.round() as i32
+        // normalize weighted values
+        weighted_base_height /= total_weight;
+        weighted_variation /= total_weight;
+
+        // final calculation of height with perlin noise
+        let terrain_noise = self
+            .perlin
+            .get([x as f64 * Self::SCALE, z as f64 * Self::SCALE]);
+        let interpolated_height = weighted_base_height + (weighted_variation * terrain_noise);
+
+        interpolated_height.round() as i32
+    }
 }
 
 pub fn generate_chunk(chunk_pos: IVec3, seed: u32) -> ServerChunk {
-    let perlin = Perlin::new(seed);
-    let temp_perlin = Perlin::new(seed + 1);
-    let humidity_perlin = Perlin::new(seed + 2);
+    let noise = TerrainNoise::new(seed);
 
-    let scale = 0.1;
-    let biome_scale = 0.02;
     let cx = chunk_pos.x;
     let cy = chunk_pos.y;
     let cz = chunk_pos.z;
 
     let mut chunk = ServerChunk {
-        map: HashMap::new(),
+        map: ChunkBlocks::new(),
         ts: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64,
     };
 
+    // Nothing below the bedrock
+    if CHUNK_SIZE * (cy + 1) <= WORLD_MIN_Y {
+        return chunk;
+    }
+
+    let mut heights = [[0; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
     for dx in 0..CHUNK_SIZE {
         for dz in 0..CHUNK_SIZE {
             let x = CHUNK_SIZE * cx + dx;
             let z = CHUNK_SIZE * cz + dz;
 
-            // calculate temperature and humidity
-            let temperature =
-                (temp_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-            let humidity =
-                (humidity_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-
-            // get biome regarding the two values
-            let biome_type = determine_biome(temperature, humidity);
-            let biome = get_biome_data(biome_type);
-
-            // get terrain height
-            let terrain_height = interpolated_height(
-                x,
-                z,
-                biome_scale,
-                &perlin,
-                &temp_perlin,
-                &humidity_perlin,
-                scale,
-            );
+            let biome = get_biome_data(noise.biome_at(x, z));
+            let terrain_height = noise.height_at(x, z);
+            heights[dx as usize][dz as usize] = terrain_height;
+
+            // sand where the land meets the water
+            let (surface_block, sub_surface_block) = if terrain_height <= SEA_LEVEL + BEACH_HEIGHT {
+                (BlockId::SAND, BlockId::SAND)
+            } else {
+                (biome.surface_block, biome.sub_surface_block)
+            };
 
             // generate blocs
             for dy in 0..CHUNK_SIZE {
                 let y = CHUNK_SIZE * cy + dy;
 
-                if y > terrain_height {
+                if y > terrain_height.max(SEA_LEVEL) {
                     break;
                 }
+                if y < WORLD_MIN_Y {
+                    continue;
+                }
 
-                let block = if y == 0 {
-                    BlockId::Bedrock
+                let block = if y == WORLD_MIN_Y {
+                    BlockId::BEDROCK
                 } else if y < terrain_height - 4 {
-                    BlockId::Stone
+                    BlockId::STONE
                 } else if y < terrain_height {
-                    biome.sub_surface_block
+                    sub_surface_block
                 } else if y == terrain_height {
-                    biome.surface_block
+                    surface_block
                 } else {
-                    panic!();
+                    BlockId::WATER
                 };
 
                 let block_pos = IVec3::new(dx, dy, dz);
 
-                chunk.map.insert(
-                    block_pos.clone(),
+                chunk.map.set(
+                    &block_pos,
                     BlockData::new(block, false, BlockDirection::Front),
                 );
-
-                // Add flora in some biomes
-                if y == terrain_height && terrain_height >= 1 {
-                    match biome_type {
-                        BiomeType::Forest => {
-                            let tree_chance = rand::random::<f32>();
-                            if tree_chance < 0.05 {
-                                // ensure the area above is clear before generating the tree
-                                let above_surface_pos = IVec3::new(dx, terrain_height + 1, dz);
-                                if !chunk.map.contains_key(&above_surface_pos) {
-                                    generate_tree(
-                                        &mut chunk,
-                                        dx,
-                                        dy + 1,
-                                        dz,
-                                        BlockId::OakLog,
-                                        BlockId::OakLeaves,
-                                    );
-                                }
-                            } else if tree_chance < 0.075 {
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(
-                                        BlockId::Dandelion,
-                                        false,
-                                        BlockDirection::Front,
-                                    ),
-                                );
-                            } else if tree_chance < 0.1 {
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(BlockId::Poppy, false, BlockDirection::Front),
-                                );
-                            }
-                        }
-                        BiomeType::IcePlain => {
-                            let tree_chance = rand::random::<f32>();
-                            if tree_chance < 0.05 {
-                                // ensure the area above is clear before generating the tree
-                                let above_surface_pos = IVec3::new(dx, terrain_height + 1, dz);
-                                if !chunk.map.contains_key(&above_surface_pos) {
-                                    generate_tree(
-                                        &mut chunk,
-                                        dx,
-                                        dy + 1,
-                                        dz,
-                                        BlockId::SpruceLog,
-                                        BlockId::SpruceLeaves,
-                                    );
-                                }
-                            }
-                        }
-                        BiomeType::Plains => {
-                            let flower_chance = rand::random::<f32>();
-                            if flower_chance < 0.075 {
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(
-                                        BlockId::Dandelion,
-                                        false,
-                                        BlockDirection::Front,
-                                    ),
-                                );
-                            } else if flower_chance < 0.1 {
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(BlockId::Poppy, false, BlockDirection::Front),
-                                );
-                            }
-                        }
-                        _ => {}
-                    }
-                }
             }
         }
     }
+
+    place_ores(&mut chunk, chunk_pos, seed);
+    carve_caves(&mut chunk, chunk_pos, &CaveNoise::new(seed), &heights);
+
+    // Trees and other structures, which may come from the neighbouring chunks
+    place_structures(&mut chunk, chunk_pos, seed, &noise);
     chunk
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// FNV-1a hash of the blocks of a chunk, stable across runs and platforms
+    fn chunk_hash(chunk: &ServerChunk) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (local_pos, block) in chunk.map.iter() {
+            for byte in bincode::serialize(&(local_pos, block)).unwrap() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    /// Hash of a few columns of chunks around the surface
+    fn world_hash(seed: u32) -> u64 {
+        let mut hash = 0;
+        for (x, z) in [(0, 0), (7, -12), (-20, 31)] {
+            for y in -5..=6 {
+                let chunk = generate_chunk(IVec3::new(x, y, z), seed);
+                hash = splitmix64(hash ^ chunk_hash(&chunk));
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let position = IVec3::new(3, 4, -2);
+        let first = generate_chunk(position, 42);
+        let second = generate_chunk(position, 42);
+        assert_eq!(chunk_hash(&first), chunk_hash(&second));
+    }
+
+    #[test]
+    fn generation_matches_golden_hashes() {
+        // If the generation changes on purpose, update these values
+        assert_eq!(world_hash(0), 16965182234293177678);
+        assert_eq!(world_hash(42), 13263072783725052573);
+        assert_eq!(world_hash(987_654_321), 6623854762338229468);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::init::TickCounter;
+use crate::world::loading::ChunkGenerationQueue;
+use bevy::prelude::*;
+use shared::world::{
+    global_block_to_chunk_pos, BlockData, ServerWorldMap, MAX_LIQUID_LEVEL, SIX_OFFSETS,
+};
+use std::collections::HashSet;
+
+/// Ticks between two steps of the liquids
+const LIQUID_FLOW_INTERVAL: u64 = 15;
+/// Positions updated in a single step, at most. The others wait for the next one
+const MAX_LIQUID_UPDATES_PER_STEP: usize = 4096;
+/// Level of a liquid falling down. It spreads again once it lands
+const FALLING_LIQUID_LEVEL: u8 = MAX_LIQUID_LEVEL - 1;
+
+const HORIZONTAL_OFFSETS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// Positions where a liquid may have to flow in, or to dry up
+#[derive(Resource, Default)]
+pub struct LiquidUpdates {
+    pending: HashSet<IVec3>,
+}
+
+impl LiquidUpdates {
+    /// Schedules a position and its neighbours, after the block there changed
+    pub fn schedule_around(&mut self, position: IVec3) {
+        self.pending.insert(position);
+        for offset in SIX_OFFSETS.iter() {
+            self.pending.insert(position + *offset);
+        }
+    }
+}
+
+/// Liquids only spread sideways once they cannot fall any lower
+fn spreads_sideways(world_map: &ServerWorldMap, position: IVec3) -> bool {
+    match world_map.get_block_by_coordinates(&(position + IVec3::NEG_Y)) {
+        None => false,
+        Some(below) => !below.id.is_liquid() || below.is_liquid_source(),
+    }
+}
+
+/// Liquid flowing into a position from the blocks around it, if any
+fn incoming_liquid(world_map: &ServerWorldMap, position: IVec3) -> Option<BlockData> {
+    // Liquids flow down first
+    if let Some(above) = world_map.get_block_by_coordinates(&(position + IVec3::Y)) {
+        if above.id.is_liquid() {
+            return Some(BlockData::new_liquid(above.id, FALLING_LIQUID_LEVEL));
+        }
+    }
+
+    let mut incoming: Option<BlockData> = None;
+    for offset in HORIZONTAL_OFFSETS {
+        let neighbor_pos = position + offset;
+        let Some(neighbor) = world_map.get_block_by_coordinates(&neighbor_pos) else {
+            continue;
+        };
+        if !neighbor.id.is_liquid()
+            || neighbor.level <= 1
+            || !spreads_sideways(world_map, neighbor_pos)
+        {
+            continue;
+        }
+        let level = neighbor.level - 1;
+        if incoming.is_none_or(|liquid| level > liquid.level) {
+            incoming = Some(BlockData::new_liquid(neighbor.id, level));
+        }
+    }
+    incoming
+}
+
+/// Makes the liquids flow around the positions that changed since the last step.\
+/// Sources never change, flowing liquids follow the ones they come from
+pub fn flow_liquids(
+    mut updates: ResMut<LiquidUpdates>,
+    mut world_map: ResMut<ServerWorldMap>,
+    queue: Res<ChunkGenerationQueue>,
+    tick_counter: Res<TickCounter>,
+) {
+    if tick_counter.tick % LIQUID_FLOW_INTERVAL != 0 || updates.pending.is_empty() {
+        return;
+    }
+
+    let positions: Vec<IVec3> = updates
+        .pending
+        .iter()
+        .take(MAX_LIQUID_UPDATES_PER_STEP)
+        .copied()
+        .collect();
+
+    // Every change of a step is computed from the same state
+    let mut changes = Vec::new();
+    for position in positions {
+        updates.pending.remove(&position);
+
+        // Liquids don't flow into chunks that are not generated yet
+        if !queue.is_ready(&world_map, &global_block_to_chunk_pos(&position)) {
+            continue;
+        }
+
+        let current = world_map.get_block_by_coordinates(&position).copied();
+        if let Some(block) = current {
+            if !block.id.is_liquid() || block.is_liquid_source() {
+                continue;
+            }
+        }
+
+        let expected = incoming_liquid(&world_map, position);
+        if expected != current {
+            changes.push((position, expected));
+        }
+    }
+
+    for (position, block) in changes {
+        match block {
+            Some(block) => world_map.set_block(&position, block),
+            None => {
+                world_map.remove_block_by_coordinates(&position);
+            }
+        }
+        updates.schedule_around(position);
+    }
+}
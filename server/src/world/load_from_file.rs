@@ -1,44 +1,75 @@
 use bevy::prelude::*;
 use ron::de::from_str;
 use shared::world::data::{ServerWorldMap, WorldSeed};
-use shared::world::get_game_folder;
+use shared::world::legacy::LegacyBlockData;
+use shared::world::{get_game_folder, ChunkBlocks, ServerChunk};
 use shared::GameFolderPaths;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::world::data::SAVE_PATH;
-use std::path::PathBuf;
+use crate::world::data::{get_world_folder, WorldMetadata, SAVE_PATH, WORLD_METADATA_FILE};
+use crate::world::region::RegionStorage;
+use crate::world::save::save_world_metadata;
 
-#[derive(serde::Serialize, serde::Deserialize)]
 pub struct WorldData {
     pub seed: WorldSeed,
     pub map: ServerWorldMap,
     pub time: u64,
 }
 
-/// Charge les données combinées (carte et graine) d'un fichier
+/// Format of the single-file RON saves, before region files were introduced
+#[derive(serde::Deserialize)]
+pub struct LegacyWorldData {
+    pub seed: WorldSeed,
+    pub map: LegacyWorldMap,
+    pub time: u64,
+}
+
+/// Only the chunks of the legacy world map are converted, other fields are ignored
+#[derive(serde::Deserialize)]
+pub struct LegacyWorldMap {
+    pub map: HashMap<IVec3, LegacyChunk>,
+}
+
+/// Legacy chunks stored each block in a map, indexed by its local position
+#[derive(serde::Deserialize)]
+pub struct LegacyChunk {
+    pub map: HashMap<IVec3, LegacyBlockData>,
+    pub ts: u64,
+}
+
+/// Loads the world metadata (seed and time) of the given world.\
+/// Chunks are not loaded here : they are read from region files when first requested
 pub fn load_world_data(
-    file_name: &str,
+    world_name: &str,
     app: &App,
 ) -> Result<WorldData, Box<dyn std::error::Error>> {
-    // Obtenir le chemin du dossier de jeu
     let game_folder_path = app.world().get_resource::<GameFolderPaths>().unwrap();
 
-    // Construire le chemin complet du fichier
-    let file_path: PathBuf = get_game_folder(Some(&game_folder_path))
+    let world_path = get_world_folder(game_folder_path, world_name);
+    let metadata_path = world_path.join(WORLD_METADATA_FILE);
+
+    let legacy_path: PathBuf = get_game_folder(Some(game_folder_path))
         .join(SAVE_PATH)
-        .join(format!("{file_name}.ron"));
-    let path: &Path = file_path.as_path();
+        .join(format!("{world_name}.ron"));
+
+    if !metadata_path.exists() && legacy_path.exists() {
+        info!(
+            "Found legacy save {}, converting it to region files",
+            legacy_path.display()
+        );
+        convert_legacy_save(&legacy_path, &world_path)?;
+    }
 
-    // Vérifier si le fichier existe
-    if !path.exists() {
+    if !metadata_path.exists() {
         info!(
             "World data file not found: {}. Generating default world and seed.",
-            file_path.display()
+            metadata_path.display()
         );
         return Ok(WorldData {
             map: ServerWorldMap {
-                name: file_name.to_string(),
+                name: world_name.to_string(),
                 ..Default::default()
             },
             seed: WorldSeed(rand::random::<u32>()),
@@ -46,29 +77,60 @@ pub fn load_world_data(
         });
     }
 
-    // Lire le contenu du fichier
-    let contents: String = fs::read_to_string(path)?;
-    let world_data: WorldData = from_str(&contents)?; // Désérialiser les données combinées
-    Ok(world_data)
-}
+    let contents: String = fs::read_to_string(&metadata_path)?;
+    let metadata: WorldMetadata = from_str(&contents)?;
 
-pub fn load_world_map(
-    file_name: &str,
-    app: &App,
-) -> Result<ServerWorldMap, Box<dyn std::error::Error>> {
-    let world_data = load_world_data(file_name, app)?;
-    Ok(world_data.map)
+    Ok(WorldData {
+        map: ServerWorldMap {
+            name: world_name.to_string(),
+            ..Default::default()
+        },
+        seed: metadata.seed,
+        time: metadata.time,
+    })
 }
 
-pub fn load_world_time(file_name: &str, app: &App) -> Result<u64, Box<dyn std::error::Error>> {
-    let world_data = load_world_data(file_name, app)?;
-    Ok(world_data.time)
-}
+/// One-shot conversion of a legacy `<world>.ron` save into a world folder with region files.\
+/// The legacy file is kept, renamed to `<world>.ron.bak`
+pub fn convert_legacy_save(
+    legacy_path: &Path,
+    world_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let contents: String = fs::read_to_string(legacy_path)?;
+    let legacy: LegacyWorldData = from_str(&contents)?;
 
-pub fn load_world_seed(
-    file_name: &str,
-    app: &App,
-) -> Result<WorldSeed, Box<dyn std::error::Error>> {
-    let world_data = load_world_data(file_name, app)?;
-    Ok(world_data.seed)
+    fs::create_dir_all(world_path)?;
+
+    let mut chunks: HashMap<IVec3, ServerChunk> = HashMap::new();
+    for (pos, legacy_chunk) in legacy.map.map {
+        let mut blocks = ChunkBlocks::new();
+        for (local_pos, block) in legacy_chunk.map {
+            blocks.set(&local_pos, block.to_block()?);
+        }
+        chunks.insert(
+            pos,
+            ServerChunk {
+                map: blocks,
+                ts: legacy_chunk.ts,
+            },
+        );
+    }
+
+    RegionStorage::new(world_path.to_path_buf()).save_chunks(&chunks)?;
+    save_world_metadata(
+        &WorldMetadata {
+            seed: legacy.seed,
+            time: legacy.time,
+        },
+        world_path,
+    )?;
+
+    fs::rename(legacy_path, legacy_path.with_extension("ron.bak"))?;
+
+    info!(
+        "Converted legacy save ({} chunks) to {}",
+        chunks.len(),
+        world_path.display()
+    );
+    Ok(())
 }
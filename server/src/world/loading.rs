@@ -0,0 +1,135 @@
+use crate::world::generation::generate_chunk;
+use crate::world::region::RegionStorage;
+use crate::world::ClientChunkViews;
+use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use shared::world::{ServerChunk, ServerWorldMap};
+use std::collections::{HashMap, HashSet};
+
+use shared::world::data::WorldSeed;
+
+/// Chunks generated at the same time, at most
+const MAX_GENERATION_TASKS: usize = 32;
+/// Chunks read from the region files in a single tick, at most
+const MAX_CHUNKS_READ_PER_TICK: usize = 64;
+
+/// Chunks waiting to be loaded, and the ones being generated on the async compute pool
+#[derive(Resource, Default)]
+pub struct ChunkGenerationQueue {
+    /// Sorted by decreasing distance to the closest player, so the next chunk to load is the last one
+    queue: Vec<IVec3>,
+    tasks: HashMap<IVec3, Task<ServerChunk>>,
+    /// Generated chunks without any block. They are neither kept nor sent,
+    /// and are forgotten once no player sees them
+    empty: HashSet<IVec3>,
+    /// Saved chunks that could not be read. They are left out of the world instead of being
+    /// generated again, so that saving the world never overwrites them
+    unreadable: HashSet<IVec3>,
+}
+
+impl ChunkGenerationQueue {
+    /// Whether the chunk can be sent right away, or is known to be empty
+    pub fn is_ready(&self, world_map: &ServerWorldMap, position: &IVec3) -> bool {
+        world_map.map.contains_key(position) || self.empty.contains(position)
+    }
+
+    /// Replaces the queue with the chunks the clients are waiting for, the closest to a player first
+    pub fn prioritize(&mut self, views: &ClientChunkViews, world_map: &ServerWorldMap) {
+        // A block placed in an empty chunk puts it in the map
+        self.empty.retain(|position| {
+            !world_map.map.contains_key(position)
+                && views
+                    .views
+                    .values()
+                    .any(|view| view.loaded.contains(position) || view.missing.contains(position))
+        });
+
+        let mut distances: HashMap<IVec3, i32> = HashMap::new();
+        for view in views.views.values() {
+            let Some(center) = view.center else {
+                continue;
+            };
+            for position in view.missing.iter() {
+                if self.is_ready(world_map, position)
+                    || self.tasks.contains_key(position)
+                    || self.unreadable.contains(position)
+                {
+                    continue;
+                }
+                let distance = center.distance_squared(*position);
+                distances
+                    .entry(*position)
+                    .and_modify(|d| *d = (*d).min(distance))
+                    .or_insert(distance);
+            }
+        }
+
+        self.queue = distances.keys().copied().collect();
+        self.queue.sort_by_key(|position| -distances[position]);
+    }
+}
+
+/// Loads the queued chunks from their region file, or generates them in the background
+/// if they were never saved. Generated chunks are added to the world as they finish
+pub fn process_chunk_generation(
+    mut queue: ResMut<ChunkGenerationQueue>,
+    mut world_map: ResMut<ServerWorldMap>,
+    storage: Res<RegionStorage>,
+    seed: Res<WorldSeed>,
+) {
+    let finished: Vec<IVec3> = queue
+        .tasks
+        .iter()
+        .filter(|(_, task)| task.is_finished())
+        .map(|(position, _)| *position)
+        .collect();
+    for position in finished {
+        let task = queue.tasks.remove(&position).unwrap();
+        let mut chunk = block_on(task);
+
+        // Blocks may have been placed while the chunk was generated
+        if let Some(existing) = world_map.map.get(&position) {
+            for (local_pos, block) in existing.map.iter() {
+                chunk.map.set(&local_pos, *block);
+            }
+        }
+
+        if chunk.map.is_empty() {
+            queue.empty.insert(position);
+        } else {
+            world_map.map.insert(position, chunk);
+        }
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    let mut chunks_read = 0;
+    while queue.tasks.len() < MAX_GENERATION_TASKS && chunks_read < MAX_CHUNKS_READ_PER_TICK {
+        let Some(position) = queue.queue.pop() else {
+            break;
+        };
+        if queue.is_ready(&world_map, &position) || queue.tasks.contains_key(&position) {
+            continue;
+        }
+
+        chunks_read += 1;
+        match storage.load_chunk(&position) {
+            Ok(Some(chunk)) => {
+                world_map.map.insert(position, chunk);
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!(
+                    "Failed to load chunk {:?}, it is left out of the world to keep its save: {}",
+                    position, e
+                );
+                queue.unreadable.insert(position);
+                continue;
+            }
+        }
+
+        let seed = seed.0;
+        let task = pool.spawn(async move { generate_chunk(position, seed) });
+        queue.tasks.insert(position, task);
+    }
+}
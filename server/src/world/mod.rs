@@ -1,8 +1,14 @@
 pub mod broadcast;
-mod data;
+pub mod data;
 pub mod generation;
+pub mod liquids;
 pub mod load_from_file;
+pub mod loading;
+pub mod player_data;
+pub mod region;
 pub mod save;
+mod structures;
+mod underground;
 mod utils;
 
 use bevy::prelude::Event;
@@ -10,32 +16,224 @@ use bevy::prelude::EventReader;
 use bevy::prelude::IVec3;
 use bevy::prelude::ResMut;
 use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, DefaultChannel, RenetServer};
 pub use broadcast::*;
-use shared::world::BlockData;
+use shared::messages::{encode_message, BlockCorrection, PlayerId, ServerToClientMessage};
+use shared::players::{
+    block_break_time, block_overlaps_player, is_block_breakable, Inventory, INTERACTION_DISTANCE,
+};
 use shared::world::ServerWorldMap;
+use shared::world::{global_block_to_chunk_pos, BlockData, ItemStack, ItemType};
+use std::collections::HashMap;
 
+use crate::entities::dropped_items::ItemDropEvent;
+use crate::init::ServerLobby;
+use crate::inventory::{send_inventory_diff, send_inventory_slots, PlayerInventories};
+use crate::world::liquids::LiquidUpdates;
+use crate::world::loading::ChunkGenerationQueue;
+
+/// Extra reach allowed by the server, to account for network latency
+pub(crate) const REACH_TOLERANCE: f32 = 1.5;
+/// Blocks may be broken slightly earlier than expected, because of the network jitter
+const BREAK_TIME_TOLERANCE: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy)]
+pub enum BlockAction {
+    /// The player started breaking the block, which takes some time
+    StartBreaking,
+    /// The player stopped breaking the block before it broke
+    AbortBreaking,
+    /// The block is broken with the item of the inventory slot
+    Break { slot: u32 },
+    /// The block is placed from the inventory slot
+    Place { block: BlockData, slot: u32 },
+}
+
+/// Interactions of a player are handled in the order they were sent
 #[derive(Event, Debug)]
 pub struct BlockInteractionEvent {
+    pub player_id: PlayerId,
     pub position: IVec3,
-    pub block_type: Option<BlockData>, // None = suppression, Some = ajout
+    pub action: BlockAction,
 }
 
+/// Block each player is breaking, with the time they started at, in seconds
+#[derive(Resource, Default, Debug)]
+pub struct BreakingProgress {
+    started: HashMap<PlayerId, (IVec3, f64)>,
+}
+
+/// Checks an interaction against the authoritative world state.\
+/// `breaking_time` is how long the player has been breaking the block, in seconds.
+/// Returns the reason of the rejection, if any
+fn validate_block_interaction(
+    world_map: &ServerWorldMap,
+    queue: &ChunkGenerationQueue,
+    inventory: &Inventory,
+    event: &BlockInteractionEvent,
+    breaking_time: Option<f32>,
+) -> Result<(), &'static str> {
+    let Some(player_position) = world_map.player_positions.get(&event.player_id) else {
+        return Err("unknown player");
+    };
+
+    // The authoritative position lags behind the client prediction, hence the tolerance
+    let block_center = event.position.as_vec3() + Vec3::splat(0.5);
+    if block_center.distance(*player_position) > INTERACTION_DISTANCE + REACH_TOLERANCE {
+        return Err("block out of reach");
+    }
+    // The terrain of the chunk is not known yet
+    if !queue.is_ready(world_map, &global_block_to_chunk_pos(&event.position)) {
+        return Err("chunk not loaded");
+    }
+
+    match &event.action {
+        BlockAction::Place { block, slot } => {
+            match inventory.inner.get(slot) {
+                Some(stack) if stack.item_type == ItemType::Block(block.id) => {}
+                _ => return Err("block not in inventory"),
+            }
+            // Blocks can be placed in liquids
+            if world_map
+                .get_block_by_coordinates(&event.position)
+                .is_some_and(|block| !block.id.is_liquid())
+            {
+                return Err("position already occupied");
+            }
+            if block.id.has_hitbox()
+                && world_map
+                    .player_positions
+                    .values()
+                    .any(|position| block_overlaps_player(&event.position, position))
+            {
+                return Err("block overlaps a player");
+            }
+        }
+        BlockAction::Break { slot } => match world_map.get_block_by_coordinates(&event.position) {
+            None => return Err("no block to break"),
+            Some(block) if !is_block_breakable(block) => return Err("block is unbreakable"),
+            Some(block) => {
+                let required = block_break_time(block, inventory.inner.get(slot));
+                if breaking_time.is_none_or(|time| time + BREAK_TIME_TOLERANCE < required) {
+                    return Err("block broken too fast");
+                }
+            }
+        },
+        BlockAction::StartBreaking | BlockAction::AbortBreaking => {}
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn handle_block_interactions(
+    mut server: ResMut<RenetServer>,
     mut world_map: ResMut<ServerWorldMap>,
+    mut inventories: ResMut<PlayerInventories>,
+    mut liquid_updates: ResMut<LiquidUpdates>,
+    mut breaking: ResMut<BreakingProgress>,
+    queue: Res<ChunkGenerationQueue>,
+    lobby: Res<ServerLobby>,
+    time: Res<Time>,
     mut events: EventReader<BlockInteractionEvent>,
+    mut ev_item_drop: EventWriter<ItemDropEvent>,
 ) {
+    // Forget the players who left while breaking a block
+    breaking
+        .started
+        .retain(|player_id, _| lobby.players.contains_key(player_id));
+
     for event in events.read() {
-        match &event.block_type {
-            Some(block) => {
+        let client_id = ClientId::from_raw(event.player_id);
+        let Some(username) = lobby.players.get(&event.player_id) else {
+            debug!("Block interaction from unknown player {}", event.player_id);
+            continue;
+        };
+
+        let breaking_time = match event.action {
+            BlockAction::StartBreaking => {
+                breaking.started.insert(
+                    event.player_id,
+                    (event.position, time.elapsed_seconds_f64()),
+                );
+                continue;
+            }
+            BlockAction::AbortBreaking => {
+                if breaking
+                    .started
+                    .get(&event.player_id)
+                    .map(|(position, _)| *position)
+                    == Some(event.position)
+                {
+                    breaking.started.remove(&event.player_id);
+                }
+                continue;
+            }
+            BlockAction::Break { .. } => breaking
+                .started
+                .remove(&event.player_id)
+                .filter(|(position, _)| *position == event.position)
+                .map(|(_, start)| (time.elapsed_seconds_f64() - start) as f32),
+            BlockAction::Place { .. } => None,
+        };
+
+        let inventory = inventories.get_or_create(username);
+        if let Err(reason) =
+            validate_block_interaction(&world_map, &queue, inventory, event, breaking_time)
+        {
+            info!(
+                "Rejected block interaction of player {} at {:?}: {}",
+                event.player_id, event.position, reason
+            );
+
+            // Send the actual block back, so the client can roll back its prediction
+            let correction = ServerToClientMessage::BlockCorrection(BlockCorrection {
+                position: event.position,
+                block: world_map.get_block_by_coordinates(&event.position).copied(),
+            });
+            let payload = encode_message(&correction);
+            server.send_message(client_id, DefaultChannel::ReliableOrdered, payload);
+
+            // The client already consumed the item it tried to place
+            if let BlockAction::Place { slot, .. } = event.action {
+                let stack = inventory.inner.get(&slot).copied();
+                send_inventory_slots(&mut server, client_id, HashMap::from([(slot, stack)]));
+            }
+            continue;
+        }
+
+        let previous = inventory.clone();
+        match event.action {
+            BlockAction::Place { block, slot } => {
                 // Ajouter un bloc
-                world_map.set_block(&event.position, *block);
+                // Only the kind and orientation come from the client, the level is the server's own
+                let block = BlockData::new(block.id, block.flipped, block.direction);
+                inventory.remove_item_from_stack(slot, 1);
+                world_map.set_block(&event.position, block);
                 debug!("Block added at {:?}: {:?}", event.position, block);
             }
-            None => {
+            BlockAction::Break { slot } => {
                 // Supprimer un bloc
-                world_map.remove_block_by_coordinates(&event.position);
+                if let Some(block) = world_map.remove_block_by_coordinates(&event.position) {
+                    inventory.wear_tool(slot);
+                    for (item_id, nb) in block.id.get_drops(1) {
+                        ev_item_drop.send(ItemDropEvent::from_block(
+                            event.position,
+                            ItemStack {
+                                item_id,
+                                item_type: item_id.get_default_type(),
+                                nb,
+                            },
+                        ));
+                    }
+                }
                 info!("Block removed at {:?}", event.position);
             }
+            // Already handled, nothing changes in the world
+            BlockAction::StartBreaking | BlockAction::AbortBreaking => {}
         }
+        // The liquids around may have to flow in, or to dry up
+        liquid_updates.schedule_around(event.position);
+        send_inventory_diff(&mut server, client_id, inventory, &previous);
     }
 }
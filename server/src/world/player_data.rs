@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use shared::players::Inventory;
+use shared::world::legacy::LegacyInventory;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PLAYERS_FOLDER: &str = "players";
+/// File holding the inventories of all players, before each player got their own file
+const LEGACY_INVENTORIES_FILE: &str = "inventories.ron";
+
+/// Everything kept about a player between two sessions
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerData {
+    pub position: Vec3,
+    pub look_direction: Vec3,
+    pub inventory: Inventory,
+    pub is_flying: bool,
+    pub spawn_point: Vec3,
+}
+
+impl PlayerData {
+    /// Data of a player joining the world for the first time
+    pub fn new(spawn_point: Vec3) -> Self {
+        Self {
+            position: spawn_point,
+            look_direction: Vec3::NEG_Z,
+            inventory: Inventory::new(),
+            is_flying: false,
+            spawn_point,
+        }
+    }
+}
+
+/// Player data saved before the registry, with items written by name
+#[derive(Deserialize)]
+struct LegacyPlayerData {
+    position: Vec3,
+    look_direction: Vec3,
+    inventory: LegacyInventory,
+    is_flying: bool,
+    spawn_point: Vec3,
+}
+
+impl LegacyPlayerData {
+    fn to_data(&self) -> Result<PlayerData, Box<dyn std::error::Error>> {
+        Ok(PlayerData {
+            position: self.position,
+            look_direction: self.look_direction,
+            inventory: self.inventory.to_inventory()?,
+            is_flying: self.is_flying,
+            spawn_point: self.spawn_point,
+        })
+    }
+}
+
+/// Gives access to the player data files of a world, one RON file per username
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerDataStorage {
+    pub players_path: PathBuf,
+}
+
+/// Turns a username into a file name, escaping anything that could escape the players folder
+fn player_file_name(username: &str) -> String {
+    let mut name = String::with_capacity(username.len() + 4);
+    for c in username.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+            name.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    name.push_str(".ron");
+    name
+}
+
+impl PlayerDataStorage {
+    pub fn new(world_path: &std::path::Path) -> Self {
+        Self {
+            players_path: world_path.join(PLAYERS_FOLDER),
+        }
+    }
+
+    fn player_path(&self, username: &str) -> PathBuf {
+        self.players_path.join(player_file_name(username))
+    }
+
+    /// Returns `None` if the player never joined the world
+    pub fn load(&self, username: &str) -> Result<Option<PlayerData>, Box<dyn std::error::Error>> {
+        let path = self.player_path(username);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        match ron::de::from_str(&contents) {
+            Ok(data) => Ok(Some(data)),
+            // Saved before the registry, rewritten in the current format when the player leaves
+            Err(e) => match ron::de::from_str::<LegacyPlayerData>(&contents) {
+                Ok(legacy) => Ok(Some(legacy.to_data()?)),
+                Err(_) => Err(e.into()),
+            },
+        }
+    }
+
+    pub fn save(
+        &self,
+        username: &str,
+        data: &PlayerData,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.players_path)?;
+
+        let pretty_config = PrettyConfig::new()
+            .with_depth_limit(4)
+            .with_separate_tuple_members(true)
+            .with_enumerate_arrays(true);
+
+        let serialized = ron::ser::to_string_pretty(data, pretty_config)?;
+
+        // Written next to the final file first, so a crash never leaves a truncated file
+        let path = self.player_path(username);
+        let tmp_path = path.with_extension("ron.tmp");
+        fs::write(&tmp_path, serialized)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Moves the inventories of the shared file of older worlds into the player files.\
+    /// The shared file is renamed once done, so that it is only migrated once
+    pub fn migrate_legacy_inventories(
+        &self,
+        world_path: &Path,
+        spawn_point: Vec3,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = world_path.join(LEGACY_INVENTORIES_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        let contents = fs::read_to_string(&path)?;
+        // The shared file was dropped before the registry, so items are written by name
+        let inventories: HashMap<String, LegacyInventory> = ron::de::from_str(&contents)?;
+        for (username, inventory) in inventories {
+            // Players who already have their own file keep it
+            if self.player_path(&username).exists() {
+                continue;
+            }
+            let data = PlayerData {
+                inventory: inventory.to_inventory()?,
+                ..PlayerData::new(spawn_point)
+            };
+            self.save(&username, &data)?;
+            info!("Moved the inventory of {} to their player file", username);
+        }
+        fs::rename(&path, path.with_extension("ron.migrated"))?;
+        Ok(())
+    }
+}
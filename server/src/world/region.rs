@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use shared::world::{BlockData, BlockDirection, BlockId, ServerChunk};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Number of chunks stored along each axis of a region file
+pub const REGION_SIZE: i32 = 8;
+
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize;
+
+const REGION_MAGIC: &[u8; 4] = b"RCRG";
+const REGION_VERSION: u32 = 2;
+/// Version of the regions saved before liquids had a level, upgraded when read
+const REGION_VERSION_WITHOUT_LEVELS: u32 = 1;
+
+/// Magic + version, followed by one `(offset, length)` pair of u32 per chunk slot
+const HEADER_SIZE: usize = 8 + CHUNKS_PER_REGION * 8;
+/// Largest compressed chunk accepted when reading a region file
+const MAX_CHUNK_PAYLOAD: u32 = 4 * 1024 * 1024;
+
+pub const REGION_FOLDER: &str = "region";
+
+/// Gives access to the region files of a world
+///
+/// A region file groups `REGION_SIZE`³ chunks. It starts with an offset table
+/// pointing to each chunk payload, so a single chunk can be read without
+/// parsing the whole file. Payloads are bincode-encoded `ServerChunk`s
+/// compressed with zlib, one per chunk.
+#[derive(Resource, Debug, Clone)]
+pub struct RegionStorage {
+    pub world_path: PathBuf,
+}
+
+pub fn chunk_to_region_pos(chunk_pos: &IVec3) -> IVec3 {
+    IVec3::new(
+        chunk_pos.x.div_euclid(REGION_SIZE),
+        chunk_pos.y.div_euclid(REGION_SIZE),
+        chunk_pos.z.div_euclid(REGION_SIZE),
+    )
+}
+
+fn chunk_index_in_region(chunk_pos: &IVec3) -> usize {
+    let x = chunk_pos.x.rem_euclid(REGION_SIZE);
+    let y = chunk_pos.y.rem_euclid(REGION_SIZE);
+    let z = chunk_pos.z.rem_euclid(REGION_SIZE);
+    ((y * REGION_SIZE + z) * REGION_SIZE + x) as usize
+}
+
+impl RegionStorage {
+    pub fn new(world_path: PathBuf) -> Self {
+        Self { world_path }
+    }
+
+    fn region_path(&self, region_pos: &IVec3) -> PathBuf {
+        self.world_path.join(REGION_FOLDER).join(format!(
+            "r.{}.{}.{}.region",
+            region_pos.x, region_pos.y, region_pos.z
+        ))
+    }
+
+    /// Reads a single chunk from its region file.
+    /// Returns `Ok(None)` if the chunk has never been saved
+    pub fn load_chunk(
+        &self,
+        chunk_pos: &IVec3,
+    ) -> Result<Option<ServerChunk>, Box<dyn std::error::Error>> {
+        let path = self.region_path(&chunk_to_region_pos(chunk_pos));
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut file = File::open(&path)?;
+        if check_header(&mut file)? != REGION_VERSION {
+            // Older regions are rewritten with the current version once, before reading them
+            write_region(&path, &read_region(&path)?)?;
+            info!(
+                "Region {} upgraded to version {}",
+                path.display(),
+                REGION_VERSION
+            );
+            file = File::open(&path)?;
+            check_header(&mut file)?;
+        }
+
+        let index = chunk_index_in_region(chunk_pos);
+        file.seek(SeekFrom::Start(8 + index as u64 * 8))?;
+        let (offset, length) = read_table_entry(&mut file)?;
+        if offset == 0 {
+            return Ok(None);
+        }
+
+        let compressed = read_payload(&mut file, offset, length)?;
+
+        Ok(Some(decompress_chunk(&compressed)?))
+    }
+
+    /// Writes chunks to the region file at `region_pos`, along with the other chunks it holds
+    fn save_region(
+        &self,
+        region_pos: &IVec3,
+        region_chunks: &[(usize, &ServerChunk)],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.region_path(region_pos);
+
+        let mut slots = if path.exists() {
+            read_region(&path)?
+        } else {
+            vec![None; CHUNKS_PER_REGION]
+        };
+
+        for (index, chunk) in region_chunks {
+            slots[*index] = Some(compress_chunk(chunk)?);
+        }
+
+        write_region(&path, &slots)?;
+        trace!("Region {:?} saved to {}", region_pos, path.display());
+        Ok(())
+    }
+
+    /// Writes the given chunks to their region files.
+    /// Chunks already stored in a region but absent from `chunks` are kept as is.\
+    /// A region that can't be read is left untouched, the others are still saved
+    pub fn save_chunks(
+        &self,
+        chunks: &HashMap<IVec3, ServerChunk>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(self.world_path.join(REGION_FOLDER))?;
+
+        let mut regions: HashMap<IVec3, Vec<(usize, &ServerChunk)>> = HashMap::new();
+        for (pos, chunk) in chunks.iter() {
+            regions
+                .entry(chunk_to_region_pos(pos))
+                .or_default()
+                .push((chunk_index_in_region(pos), chunk));
+        }
+
+        let mut errors = Vec::new();
+        for (region_pos, region_chunks) in regions {
+            if let Err(e) = self.save_region(&region_pos, &region_chunks) {
+                error!("Failed to save region {:?}: {}", region_pos, e);
+                errors.push(format!("{:?}: {}", region_pos, e));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(format!(
+                "{} regions could not be saved ({})",
+                errors.len(),
+                errors.join(", ")
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Returns the version of the region file
+fn check_header(file: &mut File) -> Result<u32, Box<dyn std::error::Error>> {
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != REGION_MAGIC {
+        return Err("invalid region file".into());
+    }
+
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != REGION_VERSION && version != REGION_VERSION_WITHOUT_LEVELS {
+        return Err(format!("unsupported region file version: {}", version).into());
+    }
+
+    Ok(version)
+}
+
+fn read_table_entry(file: &mut File) -> Result<(u32, u32), std::io::Error> {
+    let mut entry = [0u8; 8];
+    file.read_exact(&mut entry)?;
+    Ok((
+        u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+        u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]),
+    ))
+}
+
+/// Reads the compressed payload of a chunk slot.\
+/// The table entry is checked against the file size before allocating anything
+fn read_payload(
+    file: &mut File,
+    offset: u32,
+    length: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let file_size = file.metadata()?.len();
+    if length > MAX_CHUNK_PAYLOAD
+        || (offset as u64) < HEADER_SIZE as u64
+        || offset as u64 + length as u64 > file_size
+    {
+        return Err(format!(
+            "invalid chunk entry in region file: offset {}, length {}",
+            offset, length
+        )
+        .into());
+    }
+
+    let mut data = vec![0u8; length as usize];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Reads the compressed payload of every chunk slot of a region file,
+/// upgraded to the current version
+fn read_region(path: &Path) -> Result<Vec<Option<Vec<u8>>>, Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    let version = check_header(&mut file)?;
+
+    let mut table = Vec::with_capacity(CHUNKS_PER_REGION);
+    for _ in 0..CHUNKS_PER_REGION {
+        table.push(read_table_entry(&mut file)?);
+    }
+
+    let mut slots = Vec::with_capacity(CHUNKS_PER_REGION);
+    for (offset, length) in table {
+        if offset == 0 {
+            slots.push(None);
+            continue;
+        }
+        let mut data = read_payload(&mut file, offset, length)?;
+        if version == REGION_VERSION_WITHOUT_LEVELS {
+            data = upgrade_chunk_without_levels(&data)?;
+        }
+        slots.push(Some(data));
+    }
+
+    Ok(slots)
+}
+
+fn write_region(path: &Path, slots: &[Option<Vec<u8>>]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut header: Vec<u8> = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(REGION_MAGIC);
+    header.extend_from_slice(&REGION_VERSION.to_le_bytes());
+
+    let mut body: Vec<u8> = Vec::new();
+    for slot in slots.iter() {
+        match slot {
+            Some(data) => {
+                let offset = (HEADER_SIZE + body.len()) as u32;
+                header.extend_from_slice(&offset.to_le_bytes());
+                header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                body.extend_from_slice(data);
+            }
+            None => header.extend_from_slice(&[0u8; 8]),
+        }
+    }
+
+    // Write to a temporary file first, so a crash while saving cannot corrupt the region
+    let tmp_path = path.with_extension("region.tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&header)?;
+    file.write_all(&body)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+fn compress_chunk(chunk: &ServerChunk) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let serialized = bincode::serialize(chunk)?;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serialized)?;
+    Ok(encoder.finish()?)
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut decoder = ZlibDecoder::new(data);
+    let mut serialized = Vec::new();
+    decoder.read_to_end(&mut serialized)?;
+    Ok(serialized)
+}
+
+fn decompress_chunk(data: &[u8]) -> Result<ServerChunk, Box<dyn std::error::Error>> {
+    Ok(bincode::deserialize(&decompress(data)?)?)
+}
+
+/// Block as stored by the regions without levels
+#[derive(Deserialize)]
+struct BlockDataWithoutLevel {
+    id: BlockId,
+    flipped: bool,
+    direction: BlockDirection,
+}
+
+/// Chunk as stored by the regions without levels, with the same layout as `ServerChunk`
+#[derive(Deserialize)]
+struct ChunkWithoutLevels {
+    palette: Vec<Option<BlockDataWithoutLevel>>,
+    bits_per_block: u8,
+    data: Vec<u64>,
+    count: u16,
+    ts: u64,
+}
+
+/// Turns the payload of a chunk saved without levels into the current one.\
+/// The liquids of these chunks were all sources
+fn upgrade_chunk_without_levels(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let chunk: ChunkWithoutLevels = bincode::deserialize(&decompress(data)?)?;
+    let palette: Vec<Option<BlockData>> = chunk
+        .palette
+        .into_iter()
+        .map(|block| block.map(|b| BlockData::new(b.id, b.flipped, b.direction)))
+        .collect();
+    // Bincode writes structs as the tuple of their fields, nested structs included
+    let serialized = bincode::serialize(&(
+        (palette, chunk.bits_per_block, chunk.data, chunk.count),
+        chunk.ts,
+    ))?;
+    let chunk: ServerChunk = bincode::deserialize(&serialized)?;
+    compress_chunk(&chunk)
+}
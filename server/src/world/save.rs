@@ -1,29 +1,29 @@
-use crate::init::ServerTime;
+use crate::init::{ServerLobby, ServerTime};
+use crate::inventory::PlayerInventories;
+use crate::player::PlayerMovements;
+use crate::world::data::{WorldMetadata, WORLD_METADATA_FILE};
+use crate::world::player_data::PlayerDataStorage;
+use crate::world::region::RegionStorage;
 use bevy::prelude::*;
 use ron::ser::PrettyConfig;
-use shared::world::get_game_folder;
 use shared::world::ServerWorldMap;
 use shared::world::WorldSeed;
-use shared::GameFolderPaths;
-use std::{fs::File, io::Write, path::Path};
+use std::{fs, fs::File, io::Write, path::Path};
 
 #[derive(Event)]
 pub struct SaveRequestEvent;
 
-use crate::world::data::SAVE_PATH;
-
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct WorldData {
-    pub seed: WorldSeed,
-    pub map: ServerWorldMap,
-    pub time: u64,
-}
-
-// System to save the world when "L" is pressed
+// Saves the world when requested, by a player or a command
 pub fn save_world_system(
     world_map: ResMut<ServerWorldMap>,
     world_seed: Res<WorldSeed>,
-    game_folder_path: Res<GameFolderPaths>,
+    storage: Res<RegionStorage>,
+    (lobby, movements, inventories, player_data): (
+        Res<ServerLobby>,
+        Res<PlayerMovements>,
+        Res<PlayerInventories>,
+        Res<PlayerDataStorage>,
+    ),
     time: Res<ServerTime>,
     mut event: EventReader<SaveRequestEvent>,
 ) {
@@ -35,33 +35,52 @@ pub fn save_world_system(
 
     // If a save was requested by the user
     if save_requested {
-        let world_data = WorldData {
-            map: world_map.clone(),
+        let metadata = WorldMetadata {
             seed: world_seed.clone(),
             time: time.0,
         };
 
-        // define save file path
-        let save_file_path = format!(
-            "{}{}.ron",
-            get_game_folder(Some(&game_folder_path))
-                .join(SAVE_PATH)
-                .display(),
-            world_map.name
-        );
-
-        // save seed and world data
-        if let Err(e) = save_world_data(&world_data, &save_file_path) {
+        // save seed, time and loaded chunks
+        if let Err(e) = save_world_data(&metadata, &world_map, &storage) {
             error!("Failed to save world data: {}", e);
         } else {
             info!("World data saved successfully! Name: {}", world_map.name);
         }
+
+        // Disconnected players were saved when they left
+        for (id, username) in lobby.players.iter() {
+            let Some(movement) = movements.players.get(id) else {
+                continue;
+            };
+            let data = movement.to_data(inventories.saved_inventory(username));
+            if let Err(e) = player_data.save(username, &data) {
+                error!("Failed to save data of player {}: {}", username, e);
+            }
+        }
     }
 }
 
 pub fn save_world_data(
-    world_data: &WorldData,
-    file_path: &str,
+    metadata: &WorldMetadata,
+    world_map: &ServerWorldMap,
+    storage: &RegionStorage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(&storage.world_path)?;
+
+    save_world_metadata(metadata, &storage.world_path)?;
+    storage.save_chunks(&world_map.map)?;
+
+    info!(
+        "World data saved to {} ({} chunks)",
+        storage.world_path.display(),
+        world_map.map.len()
+    );
+    Ok(())
+}
+
+pub fn save_world_metadata(
+    metadata: &WorldMetadata,
+    world_path: &Path,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // configure RON serialization
     let pretty_config = PrettyConfig::new()
@@ -69,11 +88,8 @@ pub fn save_world_data(
         .with_separate_tuple_members(true)
         .with_enumerate_arrays(true);
 
-    // serialize combined data (map + seed)
-    let serialized = ron::ser::to_string_pretty(world_data, pretty_config)?;
-    let path = Path::new(file_path);
-    let mut file = File::create(path)?;
+    let serialized = ron::ser::to_string_pretty(metadata, pretty_config)?;
+    let mut file = File::create(world_path.join(WORLD_METADATA_FILE))?;
     file.write_all(serialized.as_bytes())?;
-    info!("World data saved to {}", file_path);
     Ok(())
 }
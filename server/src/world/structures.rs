@@ -0,0 +1,163 @@
+use bevy::math::{IVec2, IVec3};
+use rand::Rng;
+use shared::world::{BiomeType, BlockData, BlockDirection, BlockId, ServerChunk};
+use shared::CHUNK_SIZE;
+
+use super::generation::{feature_rng, Feature, TerrainNoise, BEACH_HEIGHT, SEA_LEVEL};
+
+/// Horizontal distance from its column a structure can reach, in blocks.\
+/// It must stay below `CHUNK_SIZE`, only the direct neighbours of a chunk are looked at
+const MAX_STRUCTURE_RADIUS: i32 = 2;
+
+/// Something growing on the terrain, which can span several chunks
+enum Structure {
+    Flower(BlockId),
+    Tree {
+        trunk: BlockId,
+        leaves: BlockId,
+        trunk_height: i32,
+    },
+    Boulder {
+        radius: i32,
+    },
+}
+
+impl Structure {
+    /// Blocks of the structure, relative to the block above the surface
+    fn blocks(&self) -> Vec<(IVec3, BlockId)> {
+        match *self {
+            Structure::Flower(flower) => vec![(IVec3::ZERO, flower)],
+            Structure::Tree {
+                trunk,
+                leaves,
+                trunk_height,
+            } => {
+                let mut blocks: Vec<(IVec3, BlockId)> = (0..trunk_height)
+                    .map(|y| (IVec3::new(0, y, 0), trunk))
+                    .collect();
+                // leaves around the top of the trunk, and one above it
+                let leaf_start_y = trunk_height - 1;
+                for offset in [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z] {
+                    blocks.push((offset.with_y(leaf_start_y), leaves));
+                }
+                blocks.push((IVec3::new(0, leaf_start_y + 1, 0), leaves));
+                blocks
+            }
+            Structure::Boulder { radius } => {
+                // half buried in the ground
+                let mut blocks = Vec::new();
+                for x in -radius..=radius {
+                    for y in -radius..=radius {
+                        for z in -radius..=radius {
+                            let offset = IVec3::new(x, y, z);
+                            if offset.length_squared() <= radius * radius {
+                                blocks.push((offset.with_y(y - 1), BlockId::COBBLESTONE));
+                            }
+                        }
+                    }
+                }
+                blocks
+            }
+        }
+    }
+}
+
+/// Structures growing on a column of chunks, with the position of the block above the surface.\
+/// Only the ones that can reach the blocks between `min` and `max` (horizontally) are returned
+fn column_structures(
+    column: IVec2,
+    seed: u32,
+    noise: &TerrainNoise,
+    min: IVec2,
+    max: IVec2,
+) -> Vec<(IVec3, Structure)> {
+    // The random values are drawn for every block of the column, in the same order,
+    // whatever part of it is wanted
+    let column_pos = IVec3::new(column.x, 0, column.y);
+    let mut flora_rng = feature_rng(seed, column_pos, Feature::Flora);
+    let mut tree_rng = feature_rng(seed, column_pos, Feature::Trees);
+    let mut boulder_rng = feature_rng(seed, column_pos, Feature::Boulders);
+
+    let mut structures = Vec::new();
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let chance = flora_rng.gen::<f32>();
+            if chance >= 0.1 {
+                continue;
+            }
+
+            let x = CHUNK_SIZE * column.x + dx;
+            let z = CHUNK_SIZE * column.y + dz;
+            let structure = match noise.biome_at(x, z) {
+                BiomeType::Forest if chance < 0.05 => Structure::Tree {
+                    trunk: BlockId::OAK_LOG,
+                    leaves: BlockId::OAK_LEAVES,
+                    trunk_height: tree_rng.gen_range(3..=5),
+                },
+                BiomeType::Forest | BiomeType::Plains if chance < 0.075 => {
+                    Structure::Flower(BlockId::DANDELION)
+                }
+                BiomeType::Forest | BiomeType::Plains => Structure::Flower(BlockId::POPPY),
+                BiomeType::IcePlain if chance < 0.05 => Structure::Tree {
+                    trunk: BlockId::SPRUCE_LOG,
+                    leaves: BlockId::SPRUCE_LEAVES,
+                    trunk_height: tree_rng.gen_range(3..=5),
+                },
+                BiomeType::MediumMountain | BiomeType::HighMountain if chance < 0.005 => {
+                    Structure::Boulder {
+                        radius: boulder_rng.gen_range(1..=MAX_STRUCTURE_RADIUS),
+                    }
+                }
+                _ => continue,
+            };
+
+            if x + MAX_STRUCTURE_RADIUS < min.x
+                || x - MAX_STRUCTURE_RADIUS > max.x
+                || z + MAX_STRUCTURE_RADIUS < min.y
+                || z - MAX_STRUCTURE_RADIUS > max.y
+            {
+                continue;
+            }
+
+            // nothing grows under the water, nor on the beaches
+            let surface = noise.height_at(x, z);
+            if surface <= SEA_LEVEL + BEACH_HEIGHT {
+                continue;
+            }
+            structures.push((IVec3::new(x, surface + 1, z), structure));
+        }
+    }
+    structures
+}
+
+/// Places the parts of the structures that overlap a chunk.\
+/// The structures of the neighbouring columns are evaluated again from their own seed,
+/// so a structure is the same whichever of its chunks is generated first
+pub fn place_structures(
+    chunk: &mut ServerChunk,
+    chunk_pos: IVec3,
+    seed: u32,
+    noise: &TerrainNoise,
+) {
+    let origin = chunk_pos * CHUNK_SIZE;
+    let min = IVec2::new(origin.x, origin.z);
+    let max = min + IVec2::splat(CHUNK_SIZE - 1);
+
+    for column_x in chunk_pos.x - 1..=chunk_pos.x + 1 {
+        for column_z in chunk_pos.z - 1..=chunk_pos.z + 1 {
+            let column = IVec2::new(column_x, column_z);
+            for (base, structure) in column_structures(column, seed, noise, min, max) {
+                for (offset, block) in structure.blocks() {
+                    // Structures don't replace the terrain, nor the structures placed before them
+                    let local_pos = base + offset - origin;
+                    if chunk.map.get(&local_pos).is_none() {
+                        chunk.map.set(
+                            &local_pos,
+                            BlockData::new(block, false, BlockDirection::Front),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::math::IVec3;
+use noise::{NoiseFn, Perlin};
+use rand::Rng;
+use shared::world::{BlockData, BlockDirection, BlockId, ServerChunk, SIX_OFFSETS};
+use shared::{CHUNK_SIZE, WORLD_MIN_Y};
+
+use super::generation::{feature_rng, Feature};
+
+/// Caves stay this many blocks below the surface, so that what grows on it has ground to stand on
+const CAVE_SURFACE_MARGIN: i32 = 6;
+
+const CHEESE_SCALE: f64 = 0.03;
+/// Noise value above which the large caves are carved
+const CHEESE_THRESHOLD: f64 = 0.45;
+const SPAGHETTI_SCALE: f64 = 0.05;
+/// Tunnels are carved where both spaghetti noises are close to zero
+const SPAGHETTI_THICKNESS: f64 = 0.08;
+
+/// Ores spread in veins, in the stone between two heights
+struct OreVein {
+    block: BlockId,
+    min_y: i32,
+    max_y: i32,
+    /// Veins tried in every chunk of the height range
+    veins_per_chunk: u32,
+    /// Blocks of a vein, at most
+    size: u32,
+}
+
+const ORE_VEINS: [OreVein; 3] = [
+    OreVein {
+        block: BlockId::COAL_ORE,
+        min_y: -16,
+        max_y: 96,
+        veins_per_chunk: 8,
+        size: 10,
+    },
+    OreVein {
+        block: BlockId::IRON_ORE,
+        min_y: -48,
+        max_y: 48,
+        veins_per_chunk: 5,
+        size: 7,
+    },
+    OreVein {
+        block: BlockId::GOLD_ORE,
+        min_y: WORLD_MIN_Y,
+        max_y: 0,
+        veins_per_chunk: 2,
+        size: 5,
+    },
+];
+
+/// Noises the caves are carved from
+pub(super) struct CaveNoise {
+    /// Large open rooms
+    cheese: Perlin,
+    /// Long winding tunnels
+    spaghetti: [Perlin; 2],
+}
+
+impl CaveNoise {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            cheese: Perlin::new(seed.wrapping_add(3)),
+            spaghetti: [
+                Perlin::new(seed.wrapping_add(4)),
+                Perlin::new(seed.wrapping_add(5)),
+            ],
+        }
+    }
+
+    fn is_cave(&self, x: i32, y: i32, z: i32) -> bool {
+        let (x, y, z) = (x as f64, y as f64, z as f64);
+
+        // flattened vertically, rooms are wider than they are high
+        let cheese = self
+            .cheese
+            .get([x * CHEESE_SCALE, y * CHEESE_SCALE * 2.0, z * CHEESE_SCALE]);
+        if cheese > CHEESE_THRESHOLD {
+            return true;
+        }
+
+        let position = [
+            x * SPAGHETTI_SCALE,
+            y * SPAGHETTI_SCALE,
+            z * SPAGHETTI_SCALE,
+        ];
+        self.spaghetti
+            .iter()
+            .all(|noise| noise.get(position).abs() < SPAGHETTI_THICKNESS)
+    }
+}
+
+/// Replaces some of the stone of a chunk with ore veins
+pub(super) fn place_ores(chunk: &mut ServerChunk, chunk_pos: IVec3, seed: u32) {
+    let mut rng = feature_rng(seed, chunk_pos, Feature::Ores);
+    let chunk_min_y = chunk_pos.y * CHUNK_SIZE;
+
+    for ore in ORE_VEINS.iter() {
+        for _ in 0..ore.veins_per_chunk {
+            // Always drawn, so that an ore does not change the veins of the next ones
+            let mut position = IVec3::new(
+                rng.gen_range(0..CHUNK_SIZE),
+                rng.gen_range(0..CHUNK_SIZE),
+                rng.gen_range(0..CHUNK_SIZE),
+            );
+            let directions: Vec<usize> = (0..ore.size).map(|_| rng.gen_range(0..6)).collect();
+
+            let y = chunk_min_y + position.y;
+            if y < ore.min_y || y > ore.max_y {
+                continue;
+            }
+            for direction in directions {
+                if chunk.map.get(&position).map(|block| block.id) == Some(BlockId::STONE) {
+                    chunk.map.set(
+                        &position,
+                        BlockData::new(ore.block, false, BlockDirection::Front),
+                    );
+                }
+                position += SIX_OFFSETS[direction];
+            }
+        }
+    }
+}
+
+/// Carves the caves in the blocks of a chunk, under the given surface heights
+pub(super) fn carve_caves(
+    chunk: &mut ServerChunk,
+    chunk_pos: IVec3,
+    noise: &CaveNoise,
+    heights: &[[i32; CHUNK_SIZE as usize]; CHUNK_SIZE as usize],
+) {
+    let origin = chunk_pos * CHUNK_SIZE;
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let ceiling = heights[dx as usize][dz as usize] - CAVE_SURFACE_MARGIN;
+            for dy in 0..CHUNK_SIZE {
+                let y = origin.y + dy;
+                if y > ceiling {
+                    break;
+                }
+                // The bedrock stays whole
+                if y <= WORLD_MIN_Y {
+                    continue;
+                }
+                if noise.is_cave(origin.x + dx, y, origin.z + dz) {
+                    chunk.map.remove(&IVec3::new(dx, dy, dz));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use bevy_renet::renet::transport::{
+    generate_random_bytes, ConnectToken, NETCODE_KEY_BYTES, NETCODE_USER_DATA_BYTES,
+};
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::NETCODE_PROTOCOL_ID;
+
+pub type PrivateKey = [u8; NETCODE_KEY_BYTES];
+
+/// Default name of the file holding the private key of a dedicated server, in the game folder
+pub const PRIVATE_KEY_FILE: &str = "server_key.bin";
+
+/// Folder of the game folder where clients look for connect tokens, one file per server
+pub const CONNECT_TOKENS_FOLDER: &str = "connect_tokens";
+
+/// Seconds without packets before a connection using a connect token times out
+const CONNECT_TOKEN_TIMEOUT_SECONDS: i32 = 15;
+
+/// Everything a client needs to join a secure server: the connect token, and
+/// the identity it was issued for
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectTokenFile {
+    pub username: String,
+    pub client_id: u64,
+    token: Vec<u8>,
+}
+
+impl ConnectTokenFile {
+    pub fn connect_token(&self) -> Result<ConnectToken, Box<dyn std::error::Error>> {
+        Ok(ConnectToken::read(&mut self.token.as_slice())?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = fs::read(path)?;
+        Ok(bincode::options().deserialize(&bytes)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bincode::options().serialize(self)?)?;
+        Ok(())
+    }
+}
+
+/// Name of the connect token file for the server at `address`.\
+/// The colons of IPv6 addresses are not allowed in Windows file names, so they become dashes
+pub fn connect_token_file_name(address: &SocketAddr) -> String {
+    let ip = address.ip().to_string().replace(':', "-");
+    format!("{}_{}.token", ip, address.port())
+}
+
+/// Path of the connect token used by the client to join the server at `address`
+pub fn connect_token_path(game_folder_path: &str, address: &SocketAddr) -> PathBuf {
+    Path::new(game_folder_path)
+        .join(CONNECT_TOKENS_FOLDER)
+        .join(connect_token_file_name(address))
+}
+
+/// Reads the private key at `path`, generating a new one if the file does not exist
+pub fn load_or_create_private_key(path: &Path) -> Result<PrivateKey, Box<dyn std::error::Error>> {
+    if path.exists() {
+        let bytes = fs::read(path)?;
+        return bytes
+            .try_into()
+            .map_err(|_| format!("{} is not a valid private key", path.display()).into());
+    }
+
+    let key: PrivateKey = generate_random_bytes();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Only readable by the user running the server
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(&key)?;
+    Ok(key)
+}
+
+/// Encodes the username in the user data of a connect token, so the server
+/// can check the player is who they claim to be
+pub fn username_to_user_data(
+    username: &str,
+) -> Result<[u8; NETCODE_USER_DATA_BYTES], Box<dyn std::error::Error>> {
+    let bytes = username.as_bytes();
+    if bytes.is_empty() || bytes.len() > NETCODE_USER_DATA_BYTES || bytes.contains(&0) {
+        return Err(format!("Invalid username: {:?}", username).into());
+    }
+    let mut user_data = [0; NETCODE_USER_DATA_BYTES];
+    user_data[..bytes.len()].copy_from_slice(bytes);
+    Ok(user_data)
+}
+
+pub fn username_from_user_data(user_data: &[u8; NETCODE_USER_DATA_BYTES]) -> Option<String> {
+    let len = user_data
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(NETCODE_USER_DATA_BYTES);
+    String::from_utf8(user_data[..len].to_vec()).ok()
+}
+
+/// Issues a connect token allowing `username` to join the servers at `server_addresses`
+pub fn generate_connect_token(
+    private_key: &PrivateKey,
+    username: &str,
+    client_id: u64,
+    server_addresses: Vec<SocketAddr>,
+    expire_seconds: u64,
+) -> Result<ConnectTokenFile, Box<dyn std::error::Error>> {
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+    let user_data = username_to_user_data(username)?;
+
+    let connect_token = ConnectToken::generate(
+        current_time,
+        NETCODE_PROTOCOL_ID,
+        expire_seconds,
+        client_id,
+        CONNECT_TOKEN_TIMEOUT_SECONDS,
+        server_addresses,
+        Some(&user_data),
+        private_key,
+    )?;
+
+    let mut token = Vec::new();
+    connect_token.write(&mut token)?;
+
+    Ok(ConnectTokenFile {
+        username: username.to_string(),
+        client_id,
+        token,
+    })
+}
@@ -0,0 +1,69 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::world::BiomeType;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MobKind {
+    Sheep,
+    Zombie,
+}
+
+pub const MOB_KINDS: [MobKind; 2] = [MobKind::Sheep, MobKind::Zombie];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MobBehavior {
+    /// Wanders around
+    Passive,
+    /// Wanders around, and chases the players coming close
+    Hostile,
+}
+
+/// Part of the day during which a mob appears
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpawnTime {
+    Day,
+    Night,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MobDefinition {
+    pub behavior: MobBehavior,
+    /// Half of the size of the hitbox of the mob
+    pub half_extents: Vec3,
+    /// Walking speed, in blocks per second
+    pub speed: f32,
+    /// Biomes where the mob appears
+    pub biomes: &'static [BiomeType],
+    pub spawn_time: SpawnTime,
+    /// Most mobs of this kind around each player
+    pub max_around_player: usize,
+}
+
+pub fn get_mob_definition(kind: MobKind) -> MobDefinition {
+    match kind {
+        MobKind::Sheep => MobDefinition {
+            behavior: MobBehavior::Passive,
+            half_extents: Vec3::new(0.45, 0.65, 0.45),
+            speed: 2.0,
+            biomes: &[BiomeType::Plains, BiomeType::Forest, BiomeType::IcePlain],
+            spawn_time: SpawnTime::Day,
+            max_around_player: 6,
+        },
+        MobKind::Zombie => MobDefinition {
+            behavior: MobBehavior::Hostile,
+            half_extents: Vec3::new(0.3, 0.9, 0.3),
+            speed: 3.0,
+            biomes: &[
+                BiomeType::Plains,
+                BiomeType::Forest,
+                BiomeType::MediumMountain,
+                BiomeType::HighMountain,
+                BiomeType::Desert,
+                BiomeType::IcePlain,
+            ],
+            spawn_time: SpawnTime::Night,
+            max_around_player: 4,
+        },
+    }
+}
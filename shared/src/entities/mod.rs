@@ -0,0 +1,50 @@
+mod mobs;
+mod physics;
+
+pub use mobs::*;
+pub use physics::*;
+
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::world::ItemId;
+
+/// Identifies an entity on the server and on the clients
+pub type EntityId = u64;
+
+/// What an entity is, which tells the clients how to draw it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    /// Items lying in the world, that players can pick up
+    DroppedItem(ItemId),
+    Mob(MobKind),
+}
+
+impl EntityKind {
+    /// Half of the size of the hitbox of the entity
+    pub fn half_extents(&self) -> Vec3 {
+        match self {
+            EntityKind::DroppedItem(_) => Vec3::splat(0.125),
+            EntityKind::Mob(kind) => get_mob_definition(*kind).half_extents,
+        }
+    }
+}
+
+/// Components of an entity that change as it moves
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct EntityMotion {
+    /// Center of the entity
+    pub position: Vec3,
+    /// In blocks per second
+    pub velocity: Vec3,
+    /// Rotation around the vertical axis, in radians
+    pub rotation: f32,
+}
+
+/// Entity as sent to the clients, when it enters their view
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct NetworkEntity {
+    pub id: EntityId,
+    pub kind: EntityKind,
+    pub motion: EntityMotion,
+}
@@ -0,0 +1,62 @@
+use bevy::math::{IVec3, Vec3};
+
+use super::EntityMotion;
+use crate::players::GRAVITY;
+use crate::world::WorldMap;
+
+/// Fastest the entities can fall, in blocks per second
+const MAX_FALL_SPEED: f32 = 40.0;
+
+/// Whether a box centered on `position` overlaps a solid block
+pub fn box_collides(world_map: &impl WorldMap, position: Vec3, half_extents: Vec3) -> bool {
+    let min = (position - half_extents).floor().as_ivec3();
+    let max = (position + half_extents).floor().as_ivec3();
+    (min.x..=max.x).any(|x| {
+        (min.y..=max.y).any(|y| {
+            (min.z..=max.z).any(|z| {
+                world_map
+                    .get_block_by_coordinates(&IVec3::new(x, y, z))
+                    .is_some_and(|block| block.id.has_hitbox())
+            })
+        })
+    })
+}
+
+/// Moves an entity by `delta` seconds under gravity, axis by axis so that it slides along the blocks.\
+/// Returns whether the entity stands on the ground
+pub fn step_entity_physics(
+    motion: &mut EntityMotion,
+    half_extents: Vec3,
+    world_map: &impl WorldMap,
+    delta: f32,
+) -> bool {
+    // A block was placed over the entity, which is pushed on top of it
+    if box_collides(world_map, motion.position, half_extents) {
+        motion.position.y = (motion.position.y - half_extents.y).floor() + 1. + half_extents.y;
+        motion.velocity = Vec3::ZERO;
+    }
+
+    motion.velocity.y = (motion.velocity.y + GRAVITY * delta).max(-MAX_FALL_SPEED);
+    let mut on_ground = false;
+    for axis in 0..3 {
+        let mut next = motion.position;
+        next[axis] += motion.velocity[axis] * delta;
+        if !box_collides(world_map, next, half_extents) {
+            motion.position = next;
+            continue;
+        }
+
+        if axis == 1 && motion.velocity.y < 0. {
+            // Lands right on top of the block below
+            let landed = (next.y - half_extents.y).floor() + 1. + half_extents.y;
+            if landed <= motion.position.y
+                && !box_collides(world_map, motion.position.with_y(landed), half_extents)
+            {
+                motion.position.y = landed;
+            }
+            on_ground = true;
+        }
+        motion.velocity[axis] = 0.;
+    }
+    on_ground
+}
@@ -1,9 +1,14 @@
+use std::net::SocketAddr;
 use std::time::Duration;
 
+use auth::PrivateKey;
 use bevy::prelude::Resource;
 use bevy_renet::renet::{ChannelConfig, ConnectionConfig, SendType};
 
+pub mod auth;
+pub mod entities;
 pub mod messages;
+pub mod players;
 pub mod world;
 
 #[derive(Resource, Debug, Clone)]
@@ -16,10 +21,33 @@ pub struct GameFolderPaths {
 pub struct GameServerConfig {
     pub world_name: String,
     pub is_solo: bool,
+    /// Key used to check connect tokens. `None` lets anyone join without a token
+    pub private_key: Option<PrivateKey>,
+    /// Addresses clients connect to, in addition to the address the server is bound to
+    pub public_addresses: Vec<SocketAddr>,
 }
 
-pub const PROTOCOL_ID: u64 = 0;
+/// Version of the network messages, sent with every packet.\
+/// Bump it whenever a message changes
+pub const PROTOCOL_ID: u64 = 12;
+/// Identifies the game to netcode. It never changes, so that clients and servers
+/// of different versions still connect and can tell the player why they can't play together
+pub const NETCODE_PROTOCOL_ID: u64 = 0;
 pub const CHUNK_SIZE: i32 = 16;
+/// Height of the bedrock layer at the bottom of the world, nothing is generated below
+pub const WORLD_MIN_Y: i32 = -64;
+
+/// Length of a day-night cycle, in seconds
+pub const DAY_DURATION: u64 = 60;
+/// Time of the day when the sun is at its highest
+pub const NOON: u64 = DAY_DURATION * 3 / 4;
+/// Time of the day when the moon is at its highest
+pub const MIDNIGHT: u64 = DAY_DURATION / 4;
+
+/// Whether the moon is up at the given time, in seconds
+pub fn is_night(time: u64) -> bool {
+    time % DAY_DURATION < DAY_DURATION / 2
+}
 
 fn get_customized_default_channels() -> Vec<ChannelConfig> {
     let memory = 128 * 1024 * 1024;
@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 
 use super::PlayerSpawnEvent;
+use crate::world::Registry;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct AuthRegisterRequest {
     pub username: String,
+    /// Chunks the client wants around its player, horizontally
+    pub render_distance: u32,
+    /// Chunks the client wants above and below its player
+    pub vertical_render_distance: u32,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -12,4 +17,6 @@ pub struct AuthRegisterResponse {
     pub username: String,
     pub session_token: u128,
     pub spawn_event: PlayerSpawnEvent,
+    /// Blocks and items of the server
+    pub registry: Box<Registry>,
 }
@@ -1,14 +1,31 @@
-use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Ids are given by the server, in the order messages are sent
+pub type ChatMessageId = u64;
+
+/// Messages kept by the server, and sent to players when they join
+pub const CHAT_HISTORY_LENGTH: usize = 100;
+/// Longest message a player can send, in characters
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChatMessage {
+    pub id: ChatMessageId,
     pub author_name: String,
     pub date: u64, // timestamp ms
     pub content: String,
 }
 
-#[derive(Resource, Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChatConversation {
-    pub messages: Vec<ChatMessage>,
+    pub messages: VecDeque<ChatMessage>,
+}
+
+/// Chat traffic sent by the server, on the ordered channel so messages keep their order
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum ChatUpdate {
+    /// Latest messages of the conversation, sent to players when they join
+    Backlog(ChatConversation),
+    Message(ChatMessage),
 }
@@ -0,0 +1,15 @@
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{EntityId, EntityMotion, NetworkEntity};
+
+/// Changes of the entities in the chunks held by a client
+#[derive(Event, Serialize, Deserialize, Debug, Clone)]
+pub enum EntityMessage {
+    /// Entities that entered the view of the client
+    Spawn(Vec<NetworkEntity>),
+    /// Entities that moved since the last update
+    Update(Vec<(EntityId, EntityMotion)>),
+    /// Entities that disappeared, or left the view of the client
+    Despawn(Vec<EntityId>),
+}
@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::world::ItemStack;
+
+/// Slots of the player inventory that changed on the server. `None` means the slot is now empty
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct InventoryUpdate {
+    pub slots: HashMap<u32, Option<ItemStack>>,
+}
+
+/// New arrangement of the inventory, after the player moved items in the inventory UI.\
+/// `held` is the stack currently held by the cursor, if any
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct InventoryLayout {
+    pub slots: HashMap<u32, ItemStack>,
+    pub held: Option<ItemStack>,
+}
@@ -1,14 +1,21 @@
 mod auth;
 mod chat;
+mod entities;
+mod inventory;
 pub mod player;
+mod protocol;
 mod system;
 mod world;
 
+use crate::players::Inventory;
 use crate::world::BlockData;
 pub use auth::*;
-use bevy::math::{IVec3, Vec3};
+use bevy::math::IVec3;
 pub use chat::*;
+pub use entities::*;
+pub use inventory::*;
 pub use player::*;
+pub use protocol::*;
 use serde::{Deserialize, Serialize};
 pub use system::*;
 pub use world::*;
@@ -18,29 +25,58 @@ pub type PlayerId = u64;
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ClientToServerMessage {
     AuthRegisterRequest(AuthRegisterRequest),
-    ChatMessage(ChatMessage),
+    /// Content of the message, the server adds the author and the date
+    ChatMessage(String),
     Exit(ExitOrder),
     PlayerInputs(PlayerInputs),
+    /// The server sends the chunks around the player, within these distances
     WorldUpdateRequest {
-        player_chunk_position: IVec3,
         render_distance: u32,
-        requested_chunks: Vec<IVec3>,
+        vertical_render_distance: u32,
     },
     SaveWorldRequest(SaveWorldRequest),
+    /// The player started breaking the block at `position`, it is broken with a `BlockInteraction`
+    StartBreaking {
+        position: IVec3,
+    },
+    /// The player stopped breaking the block at `position` before it broke
+    AbortBreaking {
+        position: IVec3,
+    },
     BlockInteraction {
         position: IVec3,
         block_type: Option<BlockData>,
+        // Inventory slot of the item used
+        slot: u32,
+    },
+    InventoryLayout(InventoryLayout),
+    /// Crafts the recipe of the crafting grid, with the crafting table at `table` if any
+    CraftItem {
+        table: Option<IVec3>,
     },
-    SetPlayerPosition {
-        // should be deprecated in the long run
-        position: Vec3,
+    /// Throws one item of the inventory slot where the player is looking
+    DropItem {
+        slot: u32,
     },
+    DebugGetBlocks,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServerToClientMessage {
     AuthRegisterResponse(AuthRegisterResponse),
-    ChatConversation(ChatConversation),
+    /// The server asks the client to leave, the reason is shown to the player
+    Disconnect {
+        reason: String,
+    },
+    Chat(ChatUpdate),
     WorldUpdate(WorldUpdate),
     PlayerSpawn(PlayerSpawnEvent),
+    PlayerStateUpdate(PlayerStateUpdate),
+    BlockCorrection(BlockCorrection),
+    BlockChanges(BlockChanges),
+    /// Chunks that left the view of the client, which can forget about them
+    UnloadChunks(Vec<IVec3>),
+    PlayerInventory(Inventory),
+    InventoryUpdate(InventoryUpdate),
+    Entities(EntityMessage),
 }
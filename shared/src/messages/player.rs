@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::PlayerId;
+use crate::players::PlayerMovementState;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum NetworkPlayerInput {
@@ -22,9 +23,19 @@ pub struct PlayerInputs {
     pub direction: Vec3,
 }
 
+/// Authoritative movement state of a player, once the server has applied
+/// all of its inputs up to `last_input_tick`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PlayerStateUpdate {
+    pub last_input_tick: u64,
+    pub state: PlayerMovementState,
+}
+
 #[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct PlayerSpawnEvent {
     pub id: PlayerId,
     pub name: String,
     pub position: Vec3,
+    /// Direction the player is looking at
+    pub direction: Vec3,
 }
@@ -0,0 +1,71 @@
+use crate::PROTOCOL_ID;
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+
+use super::{ClientToServerMessage, ServerToClientMessage};
+
+/// Messages that can be sent over the network, on any channel
+pub trait NetworkMessage: Serialize + DeserializeOwned {}
+
+impl NetworkMessage for ClientToServerMessage {}
+impl NetworkMessage for ServerToClientMessage {}
+
+/// Every packet starts with the protocol version of its sender
+#[derive(Serialize, Deserialize)]
+struct MessageEnvelope<T> {
+    protocol_id: u64,
+    message: T,
+}
+
+#[derive(Debug)]
+pub enum MessageDecodeError {
+    /// The sender runs another version of the game
+    ProtocolMismatch {
+        expected: u64,
+        received: u64,
+    },
+    Malformed(bincode::Error),
+}
+
+impl fmt::Display for MessageDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageDecodeError::ProtocolMismatch { expected, received } => write!(
+                f,
+                "Incompatible game version (protocol {}, expected {})",
+                received, expected
+            ),
+            MessageDecodeError::Malformed(e) => write!(f, "Malformed message: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MessageDecodeError {}
+
+pub fn encode_message<T: NetworkMessage>(message: &T) -> Vec<u8> {
+    let envelope = MessageEnvelope {
+        protocol_id: PROTOCOL_ID,
+        message,
+    };
+    bincode::options().serialize(&envelope).unwrap()
+}
+
+pub fn decode_message<T: NetworkMessage>(bytes: &[u8]) -> Result<T, MessageDecodeError> {
+    // The version is read first, as the rest of the packet may have another layout
+    let protocol_id: u64 = bincode::options()
+        .allow_trailing_bytes()
+        .deserialize(bytes)
+        .map_err(MessageDecodeError::Malformed)?;
+    if protocol_id != PROTOCOL_ID {
+        return Err(MessageDecodeError::ProtocolMismatch {
+            expected: PROTOCOL_ID,
+            received: protocol_id,
+        });
+    }
+
+    let envelope: MessageEnvelope<T> = bincode::options()
+        .deserialize(bytes)
+        .map_err(MessageDecodeError::Malformed)?;
+    Ok(envelope.message)
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::world::ServerChunk;
+use crate::world::{BlockData, ServerChunk};
 use bevy::math::{IVec3, Vec3};
 use serde::{Deserialize, Serialize};
 
@@ -13,3 +13,17 @@ pub struct WorldUpdate {
     pub player_positions: HashMap<PlayerId, Vec3>,
     pub time: u64,
 }
+
+/// Blocks changed since the last update, in chunks the client already received
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockChanges {
+    /// Global position of each block, with `None` when it was removed
+    pub changes: Vec<(IVec3, Option<BlockData>)>,
+}
+
+/// Authoritative state of a block, sent back to a client whose interaction was rejected
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockCorrection {
+    pub position: IVec3,
+    pub block: Option<BlockData>,
+}
@@ -0,0 +1,35 @@
+use bevy::math::{IVec3, Vec3};
+
+use crate::world::{BlockData, ItemStack};
+
+use super::{PLAYER_HEIGHT, PLAYER_WIDTH};
+
+/// Maximum distance between a player and the block they interact with
+pub const INTERACTION_DISTANCE: f32 = 7.;
+
+pub fn is_block_breakable(block: &BlockData) -> bool {
+    block.id.get_break_time() != -1.
+}
+
+/// Seconds needed to break a breakable block, holding the `tool` stack
+pub fn block_break_time(block: &BlockData, tool: Option<&ItemStack>) -> f32 {
+    let efficiency = tool.map_or(1., |stack| {
+        stack.item_id.get_efficiency(&block.id.get_tags())
+    });
+    block.id.get_break_time() / efficiency
+}
+
+/// Whether a block placed at `block_pos` would intersect the hitbox of a player standing at `player_position`
+pub fn block_overlaps_player(block_pos: &IVec3, player_position: &Vec3) -> bool {
+    let player_min = *player_position - Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) / 2.;
+    let player_max = *player_position + Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) / 2.;
+    let block_min = block_pos.as_vec3();
+    let block_max = block_min + Vec3::ONE;
+
+    block_min.x < player_max.x
+        && block_max.x > player_min.x
+        && block_min.y < player_max.y
+        && block_max.y > player_min.y
+        && block_min.z < player_max.z
+        && block_max.z > player_min.z
+}
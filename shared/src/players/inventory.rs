@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{ItemId, ItemStack, ItemType, CRAFTING_GRID_SIZE};
+
+pub const MAX_INVENTORY_SLOTS: u32 = 4 * 9;
+/// The cells of the crafting grid are slots following the ones of the inventory
+pub const CRAFTING_GRID_FIRST_SLOT: u32 = MAX_INVENTORY_SLOTS;
+pub const CRAFTING_GRID_SLOTS: u32 = CRAFTING_GRID_SIZE * CRAFTING_GRID_SIZE;
+
+/// Slot of the cell of the crafting grid at column `x` and row `y`
+pub const fn crafting_grid_slot(x: u32, y: u32) -> u32 {
+    CRAFTING_GRID_FIRST_SLOT + y * CRAFTING_GRID_SIZE + x
+}
+
+/// Whether the slot is a cell of the crafting grid
+pub fn is_crafting_grid_slot(slot: u32) -> bool {
+    (CRAFTING_GRID_FIRST_SLOT..CRAFTING_GRID_FIRST_SLOT + CRAFTING_GRID_SLOTS).contains(&slot)
+}
+
+#[derive(Debug, Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    pub inner: HashMap<u32, ItemStack>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            inner: HashMap::new(),
+        }
+    }
+
+    /// Ajoute un item à l'inventaire du joueur\
+    /// Returns the items that did not fit, if the inventory is full
+    pub fn add_item_to_inventory(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+        for i in 0..MAX_INVENTORY_SLOTS {
+            let item_option = self.inner.get(&i);
+
+            if item_option.is_some() {
+                let existing_item = item_option.expect("Error : empty item");
+                // If not item of right type or stack already full : pass
+                if existing_item.item_id != stack.item_id
+                    || existing_item.nb >= stack.item_id.get_max_stack()
+                {
+                    continue;
+                }
+
+                stack.nb += existing_item.nb;
+            }
+
+            let inserted_stack = ItemStack {
+                item_id: stack.item_id,
+                item_type: stack.item_type,
+                nb: if stack.nb >= stack.item_id.get_max_stack() {
+                    stack.item_id.get_max_stack()
+                } else {
+                    stack.nb
+                },
+            };
+            stack.nb -= inserted_stack.nb;
+
+            // Push inserted items in right inventory slot
+            self.inner.insert(i, inserted_stack);
+
+            // If no more items to add, end loop
+            if stack.nb == 0 {
+                break;
+            }
+        }
+
+        (stack.nb > 0).then_some(stack)
+    }
+
+    /// Wears the tool of the slot out after it broke a block, the tool is lost once worn out
+    pub fn wear_tool(&mut self, slot: u32) {
+        let Some(stack) = self.inner.get_mut(&slot) else {
+            return;
+        };
+        if let ItemType::Tool { durability } = &mut stack.item_type {
+            *durability -= 1;
+            if *durability <= 0 {
+                self.inner.remove(&slot);
+            }
+        }
+    }
+
+    /// Whether `add_item_to_inventory` can store the whole stack
+    pub fn has_room_for(&self, stack: &ItemStack) -> bool {
+        let max_stack = stack.item_id.get_max_stack();
+        let room: u32 = (0..MAX_INVENTORY_SLOTS)
+            .map(|i| match self.inner.get(&i) {
+                None => max_stack,
+                Some(existing) if existing.item_id == stack.item_id => {
+                    max_stack.saturating_sub(existing.nb)
+                }
+                Some(_) => 0,
+            })
+            .sum();
+        room >= stack.nb
+    }
+
+    /// Items of the crafting grid of `size` cells wide, read row by row\
+    /// Returns `None` if some items lie outside of it
+    pub fn crafting_grid(&self, size: u32) -> Option<Vec<Option<ItemId>>> {
+        let outside = self.inner.keys().any(|slot| {
+            is_crafting_grid_slot(*slot) && {
+                let cell = slot - CRAFTING_GRID_FIRST_SLOT;
+                cell % CRAFTING_GRID_SIZE >= size || cell / CRAFTING_GRID_SIZE >= size
+            }
+        });
+        if outside {
+            return None;
+        }
+        Some(
+            (0..size * size)
+                .map(|i| {
+                    self.inner
+                        .get(&crafting_grid_slot(i % size, i / size))
+                        .map(|stack| stack.item_id)
+                })
+                .collect(),
+        )
+    }
+
+    /// Uses up one item of every cell of the crafting grid, once a recipe is crafted
+    pub fn consume_crafting_grid(&mut self) {
+        for slot in CRAFTING_GRID_FIRST_SLOT..CRAFTING_GRID_FIRST_SLOT + CRAFTING_GRID_SLOTS {
+            self.remove_item_from_stack(slot, 1);
+        }
+    }
+
+    /// Moves the items left in the crafting grid back into the inventory\
+    /// Returns the items that did not fit
+    pub fn release_crafting_grid(&mut self) -> Vec<ItemStack> {
+        let mut leftovers = Vec::new();
+        for slot in CRAFTING_GRID_FIRST_SLOT..CRAFTING_GRID_FIRST_SLOT + CRAFTING_GRID_SLOTS {
+            if let Some(stack) = self.inner.remove(&slot) {
+                leftovers.extend(self.add_item_to_inventory(stack));
+            }
+        }
+        leftovers
+    }
+
+    /// Add items to stack at specified position\
+    /// Stacks cannot exceed MAX_ITEM_STACK number of items\
+    /// Returns number of items really added to the stack
+    pub fn add_item_to_stack(
+        &mut self,
+        stack: u32,
+        mut nb: u32,
+        id: ItemId,
+        item_type: ItemType,
+    ) -> u32 {
+        let item_option = self.inner.get(&stack);
+        let mut new_item = ItemStack {
+            item_id: id,
+            nb,
+            item_type,
+        };
+
+        if let Some(item) = item_option {
+            if nb + item.nb > item.item_id.get_max_stack() {
+                nb = item.item_id.get_max_stack() - item.nb;
+            }
+            new_item.nb = nb + item.nb;
+        }
+        self.inner.insert(stack, new_item);
+        nb
+    }
+
+    /// Removes items from stack at specified position\
+    /// Stacks cannot have < 0 number of items\
+    /// Returns number of items really removed from the stack
+    pub fn remove_item_from_stack(&mut self, stack: u32, mut nb: u32) -> u32 {
+        let item_option = self.inner.get(&stack);
+
+        if let Some(&item) = item_option {
+            if nb >= item.nb {
+                nb = item.nb;
+                self.inner.remove(&stack);
+            } else {
+                self.inner.insert(
+                    stack,
+                    ItemStack {
+                        item_id: item.item_id,
+                        nb: item.nb - nb,
+                        item_type: item.item_type,
+                    },
+                );
+            }
+            return nb;
+        }
+        0
+    }
+
+    /// Returns the content of every slot that differs from `previous`
+    pub fn diff(&self, previous: &Inventory) -> HashMap<u32, Option<ItemStack>> {
+        let mut slots = HashMap::new();
+        for (slot, stack) in self.inner.iter() {
+            if previous.inner.get(slot) != Some(stack) {
+                slots.insert(*slot, Some(*stack));
+            }
+        }
+        for slot in previous.inner.keys() {
+            if !self.inner.contains_key(slot) {
+                slots.insert(*slot, None);
+            }
+        }
+        slots
+    }
+
+    /// Applies slot contents computed by `diff`
+    pub fn apply_diff(&mut self, slots: &HashMap<u32, Option<ItemStack>>) {
+        for (slot, stack) in slots.iter() {
+            match stack {
+                Some(stack) => self.inner.insert(*slot, *stack),
+                None => self.inner.remove(slot),
+            };
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod interactions;
+pub mod inventory;
+pub mod simulation;
+
+pub use interactions::*;
+pub use inventory::*;
+pub use simulation::*;
@@ -0,0 +1,190 @@
+use bevy::math::{IVec3, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::{NetworkPlayerInput, PlayerInputs};
+use crate::world::WorldMap;
+use crate::WORLD_MIN_Y;
+
+/// Number of simulation steps per second. Each `PlayerInputs` message is one step
+pub const SIMULATION_TICK_RATE: f64 = 60.0;
+pub const SIMULATION_DELTA: f32 = 1.0 / SIMULATION_TICK_RATE as f32;
+
+pub const GRAVITY: f32 = -9.8 * 4.0;
+pub const PLAYER_HEIGHT: f32 = 1.8;
+pub const PLAYER_WIDTH: f32 = 0.8;
+pub const WALK_SPEED: f32 = 5.0;
+pub const FLY_SPEED: f32 = 15.0;
+pub const JUMP_VELOCITY: f32 = 10.0;
+
+/// If the player is below this height, they are sent back to their spawn point.\
+/// Well under the bottom of the world, so that nothing inside it is ever affected
+pub const FALL_LIMIT: f32 = WORLD_MIN_Y as f32 - 64.0;
+
+/// Everything needed to step the movement of a player.
+/// Simulated by the server, and predicted by the client owning the player
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PlayerMovementState {
+    pub position: Vec3,
+    pub vertical_velocity: f32,
+    pub on_ground: bool,
+    pub is_flying: bool,
+    /// Personal respawn position of the player
+    pub spawn_point: Vec3,
+}
+
+impl PlayerMovementState {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            vertical_velocity: 0.0,
+            on_ground: true,
+            is_flying: false,
+            spawn_point: position,
+        }
+    }
+
+    pub fn toggle_fly_mode(&mut self) {
+        self.is_flying = !self.is_flying;
+        self.vertical_velocity = 0.0; // Réinitialisation de la vélocité
+    }
+}
+
+fn is_block_at_position(position: Vec3, world_map: &impl WorldMap) -> bool {
+    if let Some(block) = world_map.get_block_by_coordinates(&IVec3::new(
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    )) {
+        block.id.has_hitbox()
+    } else {
+        false
+    }
+}
+
+pub fn check_player_collision(player_position: Vec3, world_map: &impl WorldMap) -> bool {
+    // Vérification de la collision avec les pieds et la tête du joueur
+    let foot_position = Vec3::new(
+        player_position.x,
+        player_position.y - PLAYER_HEIGHT / 2.0,
+        player_position.z,
+    );
+    let head_position = Vec3::new(
+        player_position.x,
+        player_position.y + PLAYER_HEIGHT / 2.0,
+        player_position.z,
+    );
+
+    // On vérifie les coins du joueur
+    let offsets = [
+        Vec3::new(-PLAYER_WIDTH / 2.0, 0.0, -PLAYER_WIDTH / 2.0), // bas gauche devant
+        Vec3::new(PLAYER_WIDTH / 2.0, 0.0, -PLAYER_WIDTH / 2.0),  // bas droite devant
+        Vec3::new(-PLAYER_WIDTH / 2.0, 0.0, PLAYER_WIDTH / 2.0),  // bas gauche derrière
+        Vec3::new(PLAYER_WIDTH / 2.0, 0.0, PLAYER_WIDTH / 2.0),   // bas droite derrière
+    ];
+
+    // Vérifier la collision au niveau des pieds et de la tête
+    for offset in &offsets {
+        if is_block_at_position(foot_position + *offset, world_map)
+            || is_block_at_position(head_position + *offset, world_map)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Steps the movement of a player by `SIMULATION_DELTA`, following the given inputs.\
+/// `inputs.direction` is the direction the player is looking at, only its horizontal part is used
+pub fn simulate_player_movement(
+    state: &mut PlayerMovementState,
+    world_map: &impl WorldMap,
+    inputs: &PlayerInputs,
+) {
+    let delta = SIMULATION_DELTA;
+    let is_pressed = |action: NetworkPlayerInput| inputs.actions.contains(&action);
+
+    if is_pressed(NetworkPlayerInput::ToggleFlyMode) {
+        state.toggle_fly_mode();
+    }
+
+    let speed = if state.is_flying {
+        FLY_SPEED
+    } else {
+        WALK_SPEED
+    };
+
+    // flying mode
+    if state.is_flying {
+        if is_pressed(NetworkPlayerInput::FlyUp) {
+            state.position.y += speed * 2.0 * delta;
+        }
+        if is_pressed(NetworkPlayerInput::FlyDown) {
+            state.position.y -= speed * 2.0 * delta;
+        }
+    }
+
+    // Calculate movement directions relative to the look direction
+    let forward = Vec3::new(inputs.direction.x, 0.0, inputs.direction.z).normalize_or_zero();
+    let right = forward.cross(Vec3::Y);
+
+    let mut direction = Vec3::ZERO;
+    if is_pressed(NetworkPlayerInput::Backward) {
+        direction -= forward;
+    }
+    if is_pressed(NetworkPlayerInput::Forward) {
+        direction += forward;
+    }
+    if is_pressed(NetworkPlayerInput::Left) {
+        direction -= right;
+    }
+    if is_pressed(NetworkPlayerInput::Right) {
+        direction += right;
+    }
+
+    // Move the player (xz plane only), only if there are no blocks
+    let direction = direction.normalize_or_zero();
+    if direction != Vec3::ZERO {
+        // Déplacement sur l'axe X
+        let new_pos_x = state.position + Vec3::new(direction.x, 0.0, 0.0) * speed * delta;
+        if state.is_flying || !check_player_collision(new_pos_x, world_map) {
+            state.position.x = new_pos_x.x;
+        }
+
+        // Déplacement sur l'axe Z
+        let new_pos_z = state.position + Vec3::new(0.0, 0.0, direction.z) * speed * delta;
+        if state.is_flying || !check_player_collision(new_pos_z, world_map) {
+            state.position.z = new_pos_z.z;
+        }
+    }
+
+    // Handle jumping (if on the ground) and gravity, only if not flying
+    if !state.is_flying {
+        if state.on_ground && is_pressed(NetworkPlayerInput::Jump) {
+            // Player can jump only when grounded
+            state.vertical_velocity = JUMP_VELOCITY;
+            state.on_ground = false;
+        } else if !state.on_ground {
+            // Apply gravity when the player is in the air
+            state.vertical_velocity += GRAVITY * delta;
+        }
+    }
+
+    // Vérifier uniquement les collisions verticales (sol et plafond)
+    let new_y = state.position.y + state.vertical_velocity * delta;
+    if check_player_collision(state.position.with_y(new_y), world_map) {
+        // Si un bloc est détecté sous le joueur, il reste sur le bloc
+        state.on_ground = true;
+        state.vertical_velocity = 0.0;
+    } else {
+        // Si aucun bloc n'est détecté sous le joueur, il continue de tomber
+        state.position.y = new_y;
+        state.on_ground = false;
+    }
+
+    // If the player is below the world, reset their position
+    if state.position.y < FALL_LIMIT {
+        state.position = state.spawn_point;
+        state.vertical_velocity = 0.0;
+    }
+}
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
-use super::{GameElementId, ItemId};
+use super::{registry, GameElementId, ItemId};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+/// Numeric id of a block, as defined in the registry
 #[derive(
     Debug,
     Clone,
@@ -17,27 +18,60 @@ use serde::{Deserialize, Serialize};
     Hash,
     Default,
 )]
-pub enum BlockId {
-    #[default]
-    Dirt,
-    Debug,
-    Grass,
-    Stone,
-    OakLog,
-    OakPlanks,
-    OakLeaves,
-    Sand,
-    Ice,
-    Glass,
-    Bedrock,
-    Dandelion,
-    Poppy,
-    Cobblestone,
-    Snow,
-    SpruceLeaves,
-    SpruceLog,
+#[serde(transparent)]
+pub struct BlockId(pub u32);
+
+impl BlockId {
+    pub const DIRT: BlockId = BlockId(0);
+    pub const DEBUG: BlockId = BlockId(1);
+    pub const GRASS: BlockId = BlockId(2);
+    pub const STONE: BlockId = BlockId(3);
+    pub const OAK_LOG: BlockId = BlockId(4);
+    pub const OAK_PLANKS: BlockId = BlockId(5);
+    pub const OAK_LEAVES: BlockId = BlockId(6);
+    pub const SAND: BlockId = BlockId(7);
+    pub const ICE: BlockId = BlockId(8);
+    pub const GLASS: BlockId = BlockId(9);
+    pub const BEDROCK: BlockId = BlockId(10);
+    pub const DANDELION: BlockId = BlockId(11);
+    pub const POPPY: BlockId = BlockId(12);
+    pub const COBBLESTONE: BlockId = BlockId(13);
+    pub const SNOW: BlockId = BlockId(14);
+    pub const SPRUCE_LEAVES: BlockId = BlockId(15);
+    pub const SPRUCE_LOG: BlockId = BlockId(16);
+    pub const COAL_ORE: BlockId = BlockId(17);
+    pub const IRON_ORE: BlockId = BlockId(18);
+    pub const GOLD_ORE: BlockId = BlockId(19);
+    pub const WATER: BlockId = BlockId(20);
+    pub const CRAFTING_TABLE: BlockId = BlockId(21);
 }
 
+/// Blocks the game relies on, the registry must define them with these ids
+pub(crate) const BUILTIN_BLOCKS: [(BlockId, &str); 22] = [
+    (BlockId::DIRT, "Dirt"),
+    (BlockId::DEBUG, "Debug"),
+    (BlockId::GRASS, "Grass"),
+    (BlockId::STONE, "Stone"),
+    (BlockId::OAK_LOG, "OakLog"),
+    (BlockId::OAK_PLANKS, "OakPlanks"),
+    (BlockId::OAK_LEAVES, "OakLeaves"),
+    (BlockId::SAND, "Sand"),
+    (BlockId::ICE, "Ice"),
+    (BlockId::GLASS, "Glass"),
+    (BlockId::BEDROCK, "Bedrock"),
+    (BlockId::DANDELION, "Dandelion"),
+    (BlockId::POPPY, "Poppy"),
+    (BlockId::COBBLESTONE, "Cobblestone"),
+    (BlockId::SNOW, "Snow"),
+    (BlockId::SPRUCE_LEAVES, "SpruceLeaves"),
+    (BlockId::SPRUCE_LOG, "SpruceLog"),
+    (BlockId::COAL_ORE, "CoalOre"),
+    (BlockId::IRON_ORE, "IronOre"),
+    (BlockId::GOLD_ORE, "GoldOre"),
+    (BlockId::WATER, "Water"),
+    (BlockId::CRAFTING_TABLE, "CraftingTable"),
+];
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BlockDirection {
     Front,
@@ -46,61 +80,78 @@ pub enum BlockDirection {
     Left,
 }
 
+/// Level of a liquid source. Flowing liquids lose one level for every block they spread
+pub const MAX_LIQUID_LEVEL: u8 = 8;
+
 /// Data associated with a given `BlockId`
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BlockData {
     pub id: BlockId,
     pub flipped: bool,
     pub direction: BlockDirection,
+    /// Level of a liquid, from 1 to `MAX_LIQUID_LEVEL`. Always 0 for the other blocks
+    pub level: u8,
 }
 
 impl BlockData {
+    /// Liquids created this way are sources
     pub fn new(id: BlockId, flipped: bool, direction: BlockDirection) -> Self {
         BlockData {
             id,
             flipped,
             direction,
+            level: if id.is_liquid() { MAX_LIQUID_LEVEL } else { 0 },
+        }
+    }
+
+    /// Liquid flowing from a source, with the given level
+    pub fn new_liquid(id: BlockId, level: u8) -> Self {
+        BlockData {
+            level,
+            ..Self::new(id, false, BlockDirection::Front)
         }
     }
+
+    pub fn is_liquid_source(&self) -> bool {
+        self.id.is_liquid() && self.level == MAX_LIQUID_LEVEL
+    }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BlockTags {
     Solid,
     Stone,
+    Wood,
+    /// Dirt, sand and the like, dug with a shovel
+    Soil,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub enum BlockTransparency {
     Transparent,
     Liquid,
+    #[default]
     Solid,
     Decoration,
 }
 
+/// The properties of the blocks are defined in the registry
 impl BlockId {
     pub fn has_hitbox(&self) -> bool {
-        match *self {
-            BlockId::Dandelion | BlockId::Poppy => false,
-            _ => true,
-        }
+        registry().block(*self).hitbox
+    }
+
+    pub fn is_liquid(&self) -> bool {
+        self.get_visibility() == BlockTransparency::Liquid
     }
 
     pub fn is_biome_colored() -> bool {
         false
     }
 
+    /// Negative for the blocks that can't be broken
     pub fn get_break_time(&self) -> f32 {
-        match *self {
-            Self::Bedrock => -1.,
-            _ => 5.,
-        }
-    }
-
-    pub fn get_color(&self) -> [f32; 4] {
-        match *self {
-            Self::Grass => [0.1, 1.0, 0.25, 1.],
-            _ => [1., 1., 1., 1.],
-        }
+        registry().block(*self).break_time
     }
 
     pub fn get_drops(&self, nb_drops: u32) -> HashMap<ItemId, u32> {
@@ -135,34 +186,15 @@ impl BlockId {
     /// Specifies the drop table of a given block
     /// Drops are specified this way : `(relative_chance, corresponding_item, base_number)`
     pub fn get_drop_table(&self) -> Vec<(u32, ItemId, u32)> {
-        match *self {
-            BlockId::Dirt | BlockId::Grass => vec![(1, ItemId::Dirt, 1)],
-            BlockId::Stone => vec![(1, ItemId::Cobblestone, 1)],
-            BlockId::Sand => vec![(1, ItemId::Sand, 1)],
-            BlockId::OakLog => vec![(1, ItemId::OakLog, 1)],
-            BlockId::OakPlanks => vec![(1, ItemId::OakPlanks, 1)],
-            BlockId::Ice => vec![(1, ItemId::Ice, 1)],
-            BlockId::Dandelion => vec![(1, ItemId::Dandelion, 1)],
-            BlockId::Poppy => vec![(1, ItemId::Dandelion, 1)],
-            BlockId::SpruceLog => vec![(1, ItemId::SpruceLog, 1)],
-            BlockId::Snow => vec![(1, ItemId::Snowball, 4)],
-            _ => vec![],
-        }
+        registry().block(*self).drops.clone()
     }
 
     pub fn get_tags(&self) -> Vec<BlockTags> {
-        match *self {
-            BlockId::Stone => vec![BlockTags::Stone, BlockTags::Solid],
-            _ => vec![BlockTags::Solid],
-        }
+        registry().block(*self).tags.clone()
     }
 
     pub fn get_visibility(&self) -> BlockTransparency {
-        match *self {
-            Self::Dandelion | Self::Poppy => BlockTransparency::Decoration,
-            Self::Glass | Self::OakLeaves | Self::SpruceLeaves => BlockTransparency::Transparent,
-            _ => BlockTransparency::Solid,
-        }
+        registry().block(*self).visibility
     }
 }
 
@@ -0,0 +1,227 @@
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+
+use crate::CHUNK_SIZE;
+
+use super::BlockData;
+
+const BLOCKS_PER_CHUNK: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Dense storage of the blocks of a 16³ chunk
+///
+/// Each distinct block of the chunk is stored once in `palette`, and every
+/// position holds an index into it, bit-packed into `data`. Index 0 is always
+/// air, so an empty chunk costs a single palette entry and no data at all.\
+/// Indices never cross a `u64` boundary, leaving the top bits of each word unused.\
+/// Chunks coming from the network or from the disk are checked when deserialized,
+/// see `RawChunkBlocks`
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(try_from = "RawChunkBlocks")]
+pub struct ChunkBlocks {
+    palette: Vec<Option<BlockData>>,
+    bits_per_block: u8,
+    data: Vec<u64>,
+    /// Number of non-air blocks
+    count: u16,
+}
+
+impl Default for ChunkBlocks {
+    fn default() -> Self {
+        Self {
+            palette: vec![None],
+            bits_per_block: 0,
+            data: Vec::new(),
+            count: 0,
+        }
+    }
+}
+
+/// Same layout as `ChunkBlocks`, as read before being checked
+#[derive(Deserialize)]
+struct RawChunkBlocks {
+    palette: Vec<Option<BlockData>>,
+    bits_per_block: u8,
+    data: Vec<u64>,
+    count: u16,
+}
+
+impl TryFrom<RawChunkBlocks> for ChunkBlocks {
+    type Error = &'static str;
+
+    fn try_from(raw: RawChunkBlocks) -> Result<Self, Self::Error> {
+        if raw.palette.first() != Some(&None) {
+            return Err("the palette of the chunk does not start with air");
+        }
+        if raw.palette[1..].iter().any(Option::is_none) {
+            return Err("the palette of the chunk holds air more than once");
+        }
+        if raw.bits_per_block == 0 {
+            if raw.palette.len() != 1 || !raw.data.is_empty() || raw.count != 0 {
+                return Err("an empty chunk holds blocks");
+            }
+            return Ok(Self::default());
+        }
+        if raw.bits_per_block > 16 {
+            return Err("too many bits per block in the chunk");
+        }
+        if raw.palette.len() > 1 << raw.bits_per_block {
+            return Err("the palette of the chunk is too long for its bits per block");
+        }
+        let per_word = 64 / raw.bits_per_block as usize;
+        if raw.data.len() != BLOCKS_PER_CHUNK.div_ceil(per_word) {
+            return Err("wrong length of the chunk data");
+        }
+
+        let chunk = Self {
+            palette: raw.palette,
+            bits_per_block: raw.bits_per_block,
+            data: raw.data,
+            count: raw.count,
+        };
+        let mut count = 0;
+        for i in 0..BLOCKS_PER_CHUNK {
+            match chunk.read_index(i) {
+                0 => {}
+                value if value < chunk.palette.len() => count += 1,
+                _ => return Err("a block of the chunk is out of its palette"),
+            }
+        }
+        if count != chunk.count {
+            return Err("wrong number of blocks in the chunk");
+        }
+        Ok(chunk)
+    }
+}
+
+fn local_index(local_pos: &IVec3) -> Option<usize> {
+    let range = 0..CHUNK_SIZE;
+    if !range.contains(&local_pos.x)
+        || !range.contains(&local_pos.y)
+        || !range.contains(&local_pos.z)
+    {
+        return None;
+    }
+    Some(((local_pos.y * CHUNK_SIZE + local_pos.z) * CHUNK_SIZE + local_pos.x) as usize)
+}
+
+fn local_position(index: usize) -> IVec3 {
+    let index = index as i32;
+    IVec3::new(
+        index % CHUNK_SIZE,
+        index / (CHUNK_SIZE * CHUNK_SIZE),
+        (index / CHUNK_SIZE) % CHUNK_SIZE,
+    )
+}
+
+fn write_packed(data: &mut [u64], bits_per_block: u8, index: usize, value: usize) {
+    let bits = bits_per_block as usize;
+    let per_word = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    let shift = (index % per_word) * bits;
+    let word = &mut data[index / per_word];
+    *word = (*word & !(mask << shift)) | ((value as u64 & mask) << shift);
+}
+
+fn bits_for_palette_len(len: usize) -> u8 {
+    if len <= 1 {
+        0
+    } else {
+        (usize::BITS - (len - 1).leading_zeros()) as u8
+    }
+}
+
+impl ChunkBlocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read_index(&self, index: usize) -> usize {
+        if self.bits_per_block == 0 {
+            return 0;
+        }
+        let bits = self.bits_per_block as usize;
+        let per_word = 64 / bits;
+        let word = self.data[index / per_word];
+        let shift = (index % per_word) * bits;
+        ((word >> shift) & ((1u64 << bits) - 1)) as usize
+    }
+
+    fn write_index(&mut self, index: usize, value: usize) {
+        write_packed(&mut self.data, self.bits_per_block, index, value);
+    }
+
+    /// Repacks the data with a new number of bits per block
+    fn resize(&mut self, bits_per_block: u8) {
+        let per_word = 64 / bits_per_block as usize;
+        let mut data = vec![0; BLOCKS_PER_CHUNK.div_ceil(per_word)];
+        for i in 0..BLOCKS_PER_CHUNK {
+            let value = self.read_index(i);
+            if value != 0 {
+                write_packed(&mut data, bits_per_block, i, value);
+            }
+        }
+        self.data = data;
+        self.bits_per_block = bits_per_block;
+    }
+
+    fn palette_index(&mut self, block: BlockData) -> usize {
+        if let Some(i) = self.palette.iter().position(|b| *b == Some(block)) {
+            return i;
+        }
+        self.palette.push(Some(block));
+        let needed = bits_for_palette_len(self.palette.len());
+        if needed > self.bits_per_block {
+            self.resize(needed);
+        }
+        self.palette.len() - 1
+    }
+
+    /// Returns the block at the given local position, `None` for air or out of bounds positions
+    pub fn get(&self, local_pos: &IVec3) -> Option<&BlockData> {
+        let index = local_index(local_pos)?;
+        self.palette[self.read_index(index)].as_ref()
+    }
+
+    pub fn contains(&self, local_pos: &IVec3) -> bool {
+        self.get(local_pos).is_some()
+    }
+
+    /// Sets the block at the given local position. Out of bounds positions are ignored
+    pub fn set(&mut self, local_pos: &IVec3, block: BlockData) {
+        let Some(index) = local_index(local_pos) else {
+            return;
+        };
+        let value = self.palette_index(block);
+        if self.read_index(index) == 0 {
+            self.count += 1;
+        }
+        self.write_index(index, value);
+    }
+
+    /// Replaces the block at the given local position with air, returning the previous block
+    pub fn remove(&mut self, local_pos: &IVec3) -> Option<BlockData> {
+        let index = local_index(local_pos)?;
+        let previous = self.palette[self.read_index(index)]?;
+        self.write_index(index, 0);
+        self.count -= 1;
+        Some(previous)
+    }
+
+    /// Number of non-air blocks
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Iterates over every non-air block, with its local position
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, &BlockData)> + '_ {
+        (0..BLOCKS_PER_CHUNK).filter_map(move |i| {
+            self.palette[self.read_index(i)]
+                .as_ref()
+                .map(|block| (local_position(i), block))
+        })
+    }
+}
@@ -12,12 +12,13 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 
 use super::BlockData;
+use super::ChunkBlocks;
 use super::ItemId;
 use super::ItemType;
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct ServerChunk {
-    pub map: HashMap<IVec3, BlockData>,
+    pub map: ChunkBlocks,
     /// Timestamp marking the last update this chunk has received
     pub ts: u64,
 }
@@ -26,7 +27,8 @@ pub struct ServerChunk {
 pub struct ServerWorldMap {
     pub name: String,
     pub map: HashMap<IVec3, ServerChunk>,
-    pub chunks_to_update: Vec<IVec3>,
+    /// Blocks changed since the last broadcast, by global position. `None` when removed
+    pub block_changes: HashMap<IVec3, Option<BlockData>>,
     pub player_positions: HashMap<PlayerId, Vec3>,
     pub time: u64,
 }
@@ -34,7 +36,7 @@ pub struct ServerWorldMap {
 #[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct WorldSeed(pub u32);
 
-#[derive(Debug, Clone, Serialize, Deserialize, Copy)]
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq)]
 pub struct ItemStack {
     pub item_id: ItemId,
     pub item_type: ItemType,
@@ -49,6 +51,8 @@ pub enum BiomeType {
     HighMountain,
     Desert,
     IcePlain,
+    Ocean,
+    River,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -66,43 +70,57 @@ pub fn get_biome_data(biome_type: BiomeType) -> Biome {
             biome_type: BiomeType::Plains,
             base_height: 64,
             height_variation: 1,
-            surface_block: BlockId::Grass,
-            sub_surface_block: BlockId::Dirt,
+            surface_block: BlockId::GRASS,
+            sub_surface_block: BlockId::DIRT,
         },
         BiomeType::Forest => Biome {
             biome_type: BiomeType::Forest,
             base_height: 64,
             height_variation: 2,
-            surface_block: BlockId::Grass,
-            sub_surface_block: BlockId::Dirt,
+            surface_block: BlockId::GRASS,
+            sub_surface_block: BlockId::DIRT,
         },
         BiomeType::MediumMountain => Biome {
             biome_type: BiomeType::MediumMountain,
             base_height: 70,
             height_variation: 4,
-            surface_block: BlockId::Grass,
-            sub_surface_block: BlockId::Dirt,
+            surface_block: BlockId::GRASS,
+            sub_surface_block: BlockId::DIRT,
         },
         BiomeType::HighMountain => Biome {
             biome_type: BiomeType::HighMountain,
             base_height: 80,
             height_variation: 7,
-            surface_block: BlockId::Grass,
-            sub_surface_block: BlockId::Dirt,
+            surface_block: BlockId::GRASS,
+            sub_surface_block: BlockId::DIRT,
         },
         BiomeType::Desert => Biome {
             biome_type: BiomeType::Desert,
             base_height: 64,
             height_variation: 1,
-            surface_block: BlockId::Sand,
-            sub_surface_block: BlockId::Sand,
+            surface_block: BlockId::SAND,
+            sub_surface_block: BlockId::SAND,
         },
         BiomeType::IcePlain => Biome {
             biome_type: BiomeType::IcePlain,
             base_height: 64,
             height_variation: 1,
-            surface_block: BlockId::Snow,
-            sub_surface_block: BlockId::Ice,
+            surface_block: BlockId::SNOW,
+            sub_surface_block: BlockId::ICE,
+        },
+        BiomeType::Ocean => Biome {
+            biome_type: BiomeType::Ocean,
+            base_height: 48,
+            height_variation: 4,
+            surface_block: BlockId::SAND,
+            sub_surface_block: BlockId::SAND,
+        },
+        BiomeType::River => Biome {
+            biome_type: BiomeType::River,
+            base_height: 56,
+            height_variation: 1,
+            surface_block: BlockId::SAND,
+            sub_surface_block: BlockId::DIRT,
         },
     }
 }
@@ -132,9 +150,6 @@ impl ServerWorldMap {
         let kind: BlockData = *block;
 
         let chunk_pos: IVec3 = global_block_to_chunk_pos(global_block_pos);
-        let cx = chunk_pos.x;
-        let cy = chunk_pos.y;
-        let cz = chunk_pos.z;
 
         let chunk_map: &mut ServerChunk =
             self.map
@@ -143,7 +158,7 @@ impl ServerWorldMap {
         let local_block_pos: IVec3 = to_local_pos(global_block_pos);
 
         chunk_map.map.remove(&local_block_pos);
-        self.chunks_to_update.push(IVec3::new(cx, cy, cz));
+        self.block_changes.insert(*global_block_pos, None);
 
         Some(kind)
     }
@@ -160,8 +175,20 @@ impl ServerWorldMap {
         let sub_y: i32 = ((y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
         let sub_z: i32 = ((z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
 
-        chunk.map.insert(IVec3::new(sub_x, sub_y, sub_z), block);
-        self.chunks_to_update.push(IVec3::new(cx, cy, cz));
+        chunk.map.set(&IVec3::new(sub_x, sub_y, sub_z), block);
+        self.block_changes.insert(*position, Some(block));
+    }
+}
+
+/// Read access to the blocks of a world, implemented by both the server and client maps
+/// so that the player simulation can run on either side
+pub trait WorldMap {
+    fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData>;
+}
+
+impl WorldMap for ServerWorldMap {
+    fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData> {
+        ServerWorldMap::get_block_by_coordinates(self, position)
     }
 }
 
@@ -2,8 +2,9 @@ use std::fmt::Debug;
 
 use serde::{Deserialize, Serialize};
 
-use super::{BlockId, GameElementId};
+use super::{registry, BlockId, BlockTags, GameElementId};
 
+/// Numeric id of an item, as defined in the registry
 #[derive(
     Debug,
     Clone,
@@ -17,65 +18,78 @@ use super::{BlockId, GameElementId};
     Hash,
     Default,
 )]
-pub enum ItemId {
-    #[default]
-    Dirt,
-    Grass,
-    Stone,
-    OakLog,
-    OakPlanks,
-    OakLeaves,
-    Sand,
-    Ice,
-    Glass,
-    Bedrock,
-    Dandelion,
-    Poppy,
-    Cobblestone,
-    Snow,
-    Snowball,
-    SpruceLog,
+#[serde(transparent)]
+pub struct ItemId(pub u32);
+
+impl ItemId {
+    pub const DIRT: ItemId = ItemId(0);
+    pub const GRASS: ItemId = ItemId(1);
+    pub const STONE: ItemId = ItemId(2);
+    pub const OAK_LOG: ItemId = ItemId(3);
+    pub const OAK_PLANKS: ItemId = ItemId(4);
+    pub const OAK_LEAVES: ItemId = ItemId(5);
+    pub const SAND: ItemId = ItemId(6);
+    pub const ICE: ItemId = ItemId(7);
+    pub const GLASS: ItemId = ItemId(8);
+    pub const BEDROCK: ItemId = ItemId(9);
+    pub const DANDELION: ItemId = ItemId(10);
+    pub const POPPY: ItemId = ItemId(11);
+    pub const COBBLESTONE: ItemId = ItemId(12);
+    pub const SNOW: ItemId = ItemId(13);
+    pub const SNOWBALL: ItemId = ItemId(14);
+    pub const SPRUCE_LOG: ItemId = ItemId(15);
+    pub const COAL: ItemId = ItemId(16);
+    pub const RAW_IRON: ItemId = ItemId(17);
+    pub const RAW_GOLD: ItemId = ItemId(18);
 }
 
+/// Items the game relies on, the registry must define them with these ids
+pub(crate) const BUILTIN_ITEMS: [(ItemId, &str); 19] = [
+    (ItemId::DIRT, "Dirt"),
+    (ItemId::GRASS, "Grass"),
+    (ItemId::STONE, "Stone"),
+    (ItemId::OAK_LOG, "OakLog"),
+    (ItemId::OAK_PLANKS, "OakPlanks"),
+    (ItemId::OAK_LEAVES, "OakLeaves"),
+    (ItemId::SAND, "Sand"),
+    (ItemId::ICE, "Ice"),
+    (ItemId::GLASS, "Glass"),
+    (ItemId::BEDROCK, "Bedrock"),
+    (ItemId::DANDELION, "Dandelion"),
+    (ItemId::POPPY, "Poppy"),
+    (ItemId::COBBLESTONE, "Cobblestone"),
+    (ItemId::SNOW, "Snow"),
+    (ItemId::SNOWBALL, "Snowball"),
+    (ItemId::SPRUCE_LOG, "SpruceLog"),
+    (ItemId::COAL, "Coal"),
+    (ItemId::RAW_IRON, "RawIron"),
+    (ItemId::RAW_GOLD, "RawGold"),
+];
+
+/// The properties of the items are defined in the registry
 impl ItemId {
     pub fn get_max_stack(&self) -> u32 {
-        64
+        registry().item(*self).max_stack
     }
 
     pub fn get_default_type(&self) -> ItemType {
-        match *self {
-            Self::Dirt => ItemType::Block(BlockId::Dirt),
-            Self::Bedrock => ItemType::Block(BlockId::Bedrock),
-            Self::Grass => ItemType::Block(BlockId::Grass),
-            Self::Stone => ItemType::Block(BlockId::Stone),
-            Self::OakLog => ItemType::Block(BlockId::OakLog),
-            Self::OakPlanks => ItemType::Block(BlockId::OakPlanks),
-            Self::Sand => ItemType::Block(BlockId::Sand),
-            Self::Ice => ItemType::Block(BlockId::Ice),
-            Self::OakLeaves => ItemType::Block(BlockId::OakLeaves),
-            Self::Glass => ItemType::Block(BlockId::Glass),
-            Self::Dandelion => ItemType::Block(BlockId::Dandelion),
-            Self::Poppy => ItemType::Block(BlockId::Poppy),
-            Self::Cobblestone => ItemType::Block(BlockId::Cobblestone),
-            Self::Snow => ItemType::Block(BlockId::Snow),
-            Self::SpruceLog => ItemType::Block(BlockId::SpruceLog),
+        registry().item(*self).item_type
+    }
 
-            Self::Snowball => ItemType::Generic,
-        }
+    /// How many times faster the item breaks a block with these tags, 1 for the items that aren't tools for it
+    pub fn get_efficiency(&self, tags: &[BlockTags]) -> f32 {
+        registry()
+            .item(*self)
+            .efficiency
+            .iter()
+            .filter(|(tag, _)| tags.contains(tag))
+            .map(|(_, efficiency)| *efficiency)
+            .fold(1., f32::max)
     }
 }
 
 impl GameElementId for ItemId {}
 
-/// Temporary struct for deserialization purposes
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TempBlock {
-    pub id: String,
-    pub drops: Vec<(u16, String)>,
-    pub break_time: f32,
-    pub uvs: [f32; 4],
-}
-
 /// Type of armor piece
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
 pub enum ArmorType {
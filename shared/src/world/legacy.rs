@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use serde::de::{self, EnumAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use super::{registry, ArmorType, BlockData, BlockDirection, BlockId, ItemId, ItemStack, ItemType};
+use crate::players::Inventory;
+
+/// Name of a block or item, as written by the RON files saved before the registry.\
+/// Ids were enum variants back then, written by name (`Dirt`) instead of by number
+#[derive(Debug, Clone)]
+pub struct LegacyName(String);
+
+/// Identifier of a RON enum variant
+struct Identifier(String);
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdentifierVisitor;
+
+        impl Visitor<'_> for IdentifierVisitor {
+            type Value = Identifier;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an identifier")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Identifier, E> {
+                Ok(Identifier(name.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(IdentifierVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for LegacyName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl<'de> Visitor<'de> for NameVisitor {
+            type Value = LegacyName;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("the name of a block or an item")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<LegacyName, A::Error> {
+                let (Identifier(name), variant) = data.variant()?;
+                variant.unit_variant()?;
+                Ok(LegacyName(name))
+            }
+        }
+
+        // Read as a unit variant, any name is accepted and looked up in the registry afterwards.
+        // RON reads the identifiers of struct fields as strings, but not the ones of variants
+        deserializer.deserialize_enum("LegacyName", &[], NameVisitor)
+    }
+}
+
+impl LegacyName {
+    pub fn block_id(&self) -> Result<BlockId, Box<dyn Error>> {
+        registry()
+            .block_by_name(&self.0)
+            .map(|block| block.id)
+            .ok_or_else(|| format!("unknown block: {}", self.0).into())
+    }
+
+    pub fn item_id(&self) -> Result<ItemId, Box<dyn Error>> {
+        registry()
+            .item_by_name(&self.0)
+            .map(|item| item.id)
+            .ok_or_else(|| format!("unknown item: {}", self.0).into())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LegacyBlockData {
+    pub id: LegacyName,
+    pub flipped: bool,
+    pub direction: BlockDirection,
+}
+
+impl LegacyBlockData {
+    /// Liquids had no level back then, they were all sources
+    pub fn to_block(&self) -> Result<BlockData, Box<dyn Error>> {
+        Ok(BlockData::new(
+            self.id.block_id()?,
+            self.flipped,
+            self.direction,
+        ))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub enum LegacyItemType {
+    Generic,
+    Block(LegacyName),
+    Tool { durability: i16 },
+    Armor(ArmorType),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LegacyItemStack {
+    pub item_id: LegacyName,
+    pub item_type: LegacyItemType,
+    pub nb: u32,
+}
+
+impl LegacyItemStack {
+    pub fn to_stack(&self) -> Result<ItemStack, Box<dyn Error>> {
+        let item_type = match &self.item_type {
+            LegacyItemType::Generic => ItemType::Generic,
+            LegacyItemType::Block(name) => ItemType::Block(name.block_id()?),
+            LegacyItemType::Tool { durability } => ItemType::Tool {
+                durability: *durability,
+            },
+            LegacyItemType::Armor(armor) => ItemType::Armor(*armor),
+        };
+        Ok(ItemStack {
+            item_id: self.item_id.item_id()?,
+            item_type,
+            nb: self.nb,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LegacyInventory {
+    pub inner: HashMap<u32, LegacyItemStack>,
+}
+
+impl LegacyInventory {
+    pub fn to_inventory(&self) -> Result<Inventory, Box<dyn Error>> {
+        let mut inventory = Inventory::new();
+        for (slot, stack) in self.inner.iter() {
+            inventory.inner.insert(*slot, stack.to_stack()?);
+        }
+        Ok(inventory)
+    }
+}
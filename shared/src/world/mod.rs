@@ -1,9 +1,16 @@
 pub mod blocks;
+pub mod chunk;
 pub mod data;
 pub mod items;
+pub mod legacy;
+pub mod recipes;
+pub mod registry;
 mod utils;
 
 pub use blocks::*;
+pub use chunk::*;
 pub use data::*;
 pub use items::*;
+pub use recipes::*;
+pub use registry::*;
 pub use utils::*;
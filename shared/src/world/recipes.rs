@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ItemId, ItemStack};
+
+/// Size of the grid of the crafting tables
+pub const CRAFTING_GRID_SIZE: u32 = 3;
+/// Size of the grid of the inventory, without crafting table
+pub const INVENTORY_CRAFTING_GRID_SIZE: u32 = 2;
+
+/// Ingredients of a recipe, and how they must be laid out in the grid
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RecipeShape {
+    /// The ingredients must follow the pattern, which can be anywhere in the grid\
+    /// `pattern` is read row by row, `None` being an empty cell
+    Shaped {
+        width: u32,
+        height: u32,
+        pattern: Vec<Option<ItemId>>,
+    },
+    /// The ingredients can be anywhere in the grid, in any order
+    Shapeless(Vec<ItemId>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Recipe {
+    pub shape: RecipeShape,
+    pub result: ItemId,
+    pub count: u32,
+}
+
+/// Bounds `(min_x, min_y, max_x, max_y)` of the non-empty cells of a grid, read row by row
+fn occupied_bounds(cells: &[Option<ItemId>], width: u32) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (i, _) in cells.iter().enumerate().filter(|(_, cell)| cell.is_some()) {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        bounds = Some(match bounds {
+            None => (x, y, x, y),
+            Some((min_x, min_y, max_x, max_y)) => {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            }
+        });
+    }
+    bounds
+}
+
+impl Recipe {
+    /// Whether a square grid of `size` cells wide, read row by row, holds this recipe
+    pub fn matches(&self, grid: &[Option<ItemId>], size: u32) -> bool {
+        match &self.shape {
+            RecipeShape::Shaped { width, pattern, .. } => {
+                // The patterns are compared once both trimmed of their empty borders
+                let (Some(expected), Some(actual)) = (
+                    occupied_bounds(pattern, *width),
+                    occupied_bounds(grid, size),
+                ) else {
+                    return false;
+                };
+                if expected.2 - expected.0 != actual.2 - actual.0
+                    || expected.3 - expected.1 != actual.3 - actual.1
+                {
+                    return false;
+                }
+                (0..=expected.3 - expected.1).all(|dy| {
+                    (0..=expected.2 - expected.0).all(|dx| {
+                        let wanted =
+                            pattern[((expected.1 + dy) * width + expected.0 + dx) as usize];
+                        let found = grid[((actual.1 + dy) * size + actual.0 + dx) as usize];
+                        wanted == found
+                    })
+                })
+            }
+            RecipeShape::Shapeless(ingredients) => {
+                let mut remaining = ingredients.clone();
+                for item in grid.iter().flatten() {
+                    match remaining.iter().position(|ingredient| ingredient == item) {
+                        Some(index) => remaining.swap_remove(index),
+                        None => return false,
+                    };
+                }
+                remaining.is_empty()
+            }
+        }
+    }
+
+    pub fn result_stack(&self) -> ItemStack {
+        ItemStack {
+            item_id: self.result,
+            item_type: self.result.get_default_type(),
+            nb: self.count,
+        }
+    }
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// `RecipeShape` as written in the recipe file, with the items referred to by name
+#[derive(Deserialize)]
+enum RawRecipeShape {
+    /// Each character of the pattern is an item of `key`, spaces are empty cells
+    Shaped {
+        pattern: Vec<String>,
+        key: HashMap<char, String>,
+    },
+    Shapeless(Vec<String>),
+}
+
+/// Recipe as written in the recipe file
+#[derive(Deserialize)]
+pub(super) struct RawRecipe {
+    shape: RawRecipeShape,
+    result: String,
+    #[serde(default = "default_count")]
+    count: u32,
+}
+
+impl RawRecipe {
+    /// Resolves the item names with `item_ids`, and checks the recipe fits in a crafting grid
+    pub(super) fn resolve(
+        self,
+        item_ids: &HashMap<&str, ItemId>,
+    ) -> Result<Recipe, Box<dyn Error>> {
+        let item = |name: &str| -> Result<ItemId, Box<dyn Error>> {
+            item_ids.get(name).copied().ok_or_else(|| {
+                format!("recipe of {} uses unknown item {}", self.result, name).into()
+            })
+        };
+
+        let shape = match &self.shape {
+            RawRecipeShape::Shaped { pattern, key } => {
+                let height = pattern.len() as u32;
+                let width = pattern.first().map_or(0, |row| row.chars().count()) as u32;
+                if width == 0
+                    || width > CRAFTING_GRID_SIZE
+                    || height > CRAFTING_GRID_SIZE
+                    || pattern
+                        .iter()
+                        .any(|row| row.chars().count() as u32 != width)
+                {
+                    return Err(format!("recipe of {} has an invalid pattern", self.result).into());
+                }
+                let mut cells = Vec::new();
+                for symbol in pattern.iter().flat_map(|row| row.chars()) {
+                    if symbol == ' ' {
+                        cells.push(None);
+                        continue;
+                    }
+                    let Some(name) = key.get(&symbol) else {
+                        return Err(format!(
+                            "recipe of {} uses undefined symbol '{}'",
+                            self.result, symbol
+                        )
+                        .into());
+                    };
+                    cells.push(Some(item(name)?));
+                }
+                if cells.iter().all(Option::is_none) {
+                    return Err(format!("recipe of {} has no ingredient", self.result).into());
+                }
+                RecipeShape::Shaped {
+                    width,
+                    height,
+                    pattern: cells,
+                }
+            }
+            RawRecipeShape::Shapeless(names) => {
+                if names.is_empty() || names.len() as u32 > CRAFTING_GRID_SIZE * CRAFTING_GRID_SIZE
+                {
+                    return Err(format!(
+                        "recipe of {} has an invalid ingredient list",
+                        self.result
+                    )
+                    .into());
+                }
+                RecipeShape::Shapeless(
+                    names
+                        .iter()
+                        .map(|name| item(name))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        };
+
+        if self.count == 0 {
+            return Err(format!("recipe of {} crafts no item", self.result).into());
+        }
+        Ok(Recipe {
+            shape,
+            result: item(&self.result)?,
+            count: self.count,
+        })
+    }
+}
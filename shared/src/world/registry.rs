@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock, RwLockReadGuard};
+
+use serde::{Deserialize, Serialize};
+
+use super::blocks::BUILTIN_BLOCKS;
+use super::items::BUILTIN_ITEMS;
+use super::recipes::{RawRecipe, Recipe};
+use super::{ArmorType, BlockId, BlockTags, BlockTransparency, ItemId, ItemType};
+
+/// Folder of the registry files, in the data folder
+pub const REGISTRY_FOLDER: &str = "registry";
+const BLOCKS_FILE: &str = "blocks.ron";
+const ITEMS_FILE: &str = "items.ron";
+const RECIPES_FILE: &str = "recipes.ron";
+
+/// Definitions shipped with the game, used until the ones of the server are known
+const DEFAULT_BLOCKS: &str = include_str!("../../../data/registry/blocks.ron");
+const DEFAULT_ITEMS: &str = include_str!("../../../data/registry/items.ron");
+const DEFAULT_RECIPES: &str = include_str!("../../../data/registry/recipes.ron");
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(|| {
+    RwLock::new(
+        Registry::from_ron(DEFAULT_BLOCKS, DEFAULT_ITEMS, DEFAULT_RECIPES)
+            .expect("invalid default registry"),
+    )
+});
+
+/// Registry in use, the blocks and items get their properties from it
+pub fn registry() -> RwLockReadGuard<'static, Registry> {
+    REGISTRY.read().unwrap()
+}
+
+/// Replaces the registry in use, by the one of the server for instance
+pub fn set_registry(registry: Registry) {
+    *REGISTRY.write().unwrap() = registry;
+}
+
+/// Shape of the mesh of a block
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum BlockShape {
+    #[default]
+    Cube,
+    /// Two crossed planes, like the flowers
+    Cross,
+}
+
+/// Texture names of the faces of a block. The faces without their own use `all`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlockTextures {
+    pub all: String,
+    #[serde(default)]
+    pub top: Option<String>,
+    #[serde(default)]
+    pub bottom: Option<String>,
+    #[serde(default)]
+    pub front: Option<String>,
+    #[serde(default)]
+    pub back: Option<String>,
+    #[serde(default)]
+    pub left: Option<String>,
+    #[serde(default)]
+    pub right: Option<String>,
+}
+
+impl BlockTextures {
+    fn uniform(texture: &str) -> Self {
+        Self {
+            all: texture.into(),
+            top: None,
+            bottom: None,
+            front: None,
+            back: None,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlockDefinition {
+    pub id: BlockId,
+    pub name: String,
+    pub shape: BlockShape,
+    pub visibility: BlockTransparency,
+    pub hitbox: bool,
+    /// In seconds, negative for the blocks that can't be broken
+    pub break_time: f32,
+    /// `(relative_chance, corresponding_item, base_number)`
+    pub drops: Vec<(u32, ItemId, u32)>,
+    pub tags: Vec<BlockTags>,
+    pub textures: BlockTextures,
+    /// Color the textures of every face are multiplied with
+    pub tint: Option<[f32; 4]>,
+    /// Color of the top face, replacing `tint`
+    pub top_tint: Option<[f32; 4]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemDefinition {
+    pub id: ItemId,
+    pub name: String,
+    pub texture: String,
+    pub max_stack: u32,
+    pub item_type: ItemType,
+    /// Break speed multipliers of the tools, for the blocks with the given tags
+    pub efficiency: Vec<(BlockTags, f32)>,
+}
+
+fn default_hitbox() -> bool {
+    true
+}
+
+fn default_break_time() -> f32 {
+    5.
+}
+
+fn default_tags() -> Vec<BlockTags> {
+    vec![BlockTags::Solid]
+}
+
+fn default_max_stack() -> u32 {
+    64
+}
+
+/// Block as written in the registry files, where items are referred to by name
+#[derive(Deserialize)]
+struct RawBlock {
+    id: BlockId,
+    name: String,
+    #[serde(default)]
+    shape: BlockShape,
+    #[serde(default)]
+    visibility: BlockTransparency,
+    #[serde(default = "default_hitbox")]
+    hitbox: bool,
+    #[serde(default = "default_break_time")]
+    break_time: f32,
+    #[serde(default)]
+    drops: Vec<(u32, String, u32)>,
+    #[serde(default = "default_tags")]
+    tags: Vec<BlockTags>,
+    /// Defaults to the name of the block on every face
+    #[serde(default)]
+    textures: Option<BlockTextures>,
+    #[serde(default)]
+    tint: Option<[f32; 4]>,
+    #[serde(default)]
+    top_tint: Option<[f32; 4]>,
+}
+
+/// `ItemType`, with the blocks referred to by name
+#[derive(Deserialize, Default)]
+enum RawItemType {
+    #[default]
+    Generic,
+    Block(String),
+    Tool {
+        durability: i16,
+    },
+    Armor(ArmorType),
+}
+
+/// Item as written in the registry files
+#[derive(Deserialize)]
+struct RawItem {
+    id: ItemId,
+    name: String,
+    /// Defaults to the name of the item
+    #[serde(default)]
+    texture: Option<String>,
+    #[serde(default = "default_max_stack")]
+    max_stack: u32,
+    #[serde(default)]
+    item_type: RawItemType,
+    #[serde(default)]
+    efficiency: Vec<(BlockTags, f32)>,
+}
+
+/// Definitions of all the blocks and items, indexed by their numeric id, and the crafting recipes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Registry {
+    blocks: HashMap<BlockId, BlockDefinition>,
+    items: HashMap<ItemId, ItemDefinition>,
+    recipes: Vec<Recipe>,
+    /// Stands for the ids the registry doesn't know about
+    unknown_block: BlockDefinition,
+    unknown_item: ItemDefinition,
+}
+
+impl Registry {
+    /// Reads the registry files of the given folder
+    pub fn load(folder: &Path) -> Result<Self, Box<dyn Error>> {
+        let blocks = fs::read_to_string(folder.join(BLOCKS_FILE))?;
+        let items = fs::read_to_string(folder.join(ITEMS_FILE))?;
+        let recipes = fs::read_to_string(folder.join(RECIPES_FILE))?;
+        Self::from_ron(&blocks, &items, &recipes)
+    }
+
+    pub fn from_ron(blocks: &str, items: &str, recipes: &str) -> Result<Self, Box<dyn Error>> {
+        let raw_blocks: Vec<RawBlock> = ron::de::from_str(blocks)?;
+        let raw_items: Vec<RawItem> = ron::de::from_str(items)?;
+        let raw_recipes: Vec<RawRecipe> = ron::de::from_str(recipes)?;
+
+        let block_ids: HashMap<&str, BlockId> = raw_blocks
+            .iter()
+            .map(|block| (block.name.as_str(), block.id))
+            .collect();
+        let item_ids: HashMap<&str, ItemId> = raw_items
+            .iter()
+            .map(|item| (item.name.as_str(), item.id))
+            .collect();
+
+        let mut registry = Registry {
+            blocks: HashMap::new(),
+            items: HashMap::new(),
+            recipes: Vec::new(),
+            unknown_block: BlockDefinition {
+                id: BlockId::default(),
+                name: "Unknown".into(),
+                shape: BlockShape::Cube,
+                visibility: BlockTransparency::Solid,
+                hitbox: true,
+                break_time: default_break_time(),
+                drops: vec![],
+                tags: vec![],
+                textures: BlockTextures::uniform("_Default"),
+                tint: None,
+                top_tint: None,
+            },
+            unknown_item: ItemDefinition {
+                id: ItemId::default(),
+                name: "Unknown".into(),
+                texture: "_Default".into(),
+                max_stack: default_max_stack(),
+                item_type: ItemType::Generic,
+                efficiency: vec![],
+            },
+        };
+
+        for raw in raw_items.iter() {
+            let item_type = match &raw.item_type {
+                RawItemType::Generic => ItemType::Generic,
+                RawItemType::Block(name) => {
+                    let Some(block) = block_ids.get(name.as_str()) else {
+                        return Err(
+                            format!("item {} places unknown block {}", raw.name, name).into()
+                        );
+                    };
+                    ItemType::Block(*block)
+                }
+                RawItemType::Tool { durability } => ItemType::Tool {
+                    durability: *durability,
+                },
+                RawItemType::Armor(armor) => ItemType::Armor(*armor),
+            };
+            let item = ItemDefinition {
+                id: raw.id,
+                name: raw.name.clone(),
+                texture: raw.texture.clone().unwrap_or_else(|| raw.name.clone()),
+                max_stack: raw.max_stack,
+                item_type,
+                efficiency: raw.efficiency.clone(),
+            };
+            if registry.items.insert(raw.id, item).is_some() {
+                return Err(format!("duplicate item id {}", raw.id.0).into());
+            }
+        }
+
+        for raw in raw_blocks.into_iter() {
+            let mut drops = Vec::new();
+            for (chance, name, number) in raw.drops {
+                let Some(item) = item_ids.get(name.as_str()) else {
+                    return Err(format!("block {} drops unknown item {}", raw.name, name).into());
+                };
+                drops.push((chance, *item, number));
+            }
+            let block = BlockDefinition {
+                id: raw.id,
+                textures: raw
+                    .textures
+                    .unwrap_or_else(|| BlockTextures::uniform(&raw.name)),
+                name: raw.name,
+                shape: raw.shape,
+                visibility: raw.visibility,
+                hitbox: raw.hitbox,
+                break_time: raw.break_time,
+                drops,
+                tags: raw.tags,
+                tint: raw.tint,
+                top_tint: raw.top_tint,
+            };
+            if registry.blocks.insert(raw.id, block).is_some() {
+                return Err(format!("duplicate block id {}", raw.id.0).into());
+            }
+        }
+
+        for raw in raw_recipes.into_iter() {
+            registry.recipes.push(raw.resolve(&item_ids)?);
+        }
+
+        // The code refers to some blocks and items by their id
+        for (id, name) in BUILTIN_BLOCKS {
+            if registry.blocks.get(&id).map(|block| block.name.as_str()) != Some(name) {
+                return Err(format!("block {} must be defined with id {}", name, id.0).into());
+            }
+        }
+        for (id, name) in BUILTIN_ITEMS {
+            if registry.items.get(&id).map(|item| item.name.as_str()) != Some(name) {
+                return Err(format!("item {} must be defined with id {}", name, id.0).into());
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn block(&self, id: BlockId) -> &BlockDefinition {
+        self.blocks.get(&id).unwrap_or(&self.unknown_block)
+    }
+
+    pub fn item(&self, id: ItemId) -> &ItemDefinition {
+        self.items.get(&id).unwrap_or(&self.unknown_item)
+    }
+
+    pub fn block_by_name(&self, name: &str) -> Option<&BlockDefinition> {
+        self.blocks.values().find(|block| block.name == name)
+    }
+
+    pub fn item_by_name(&self, name: &str) -> Option<&ItemDefinition> {
+        self.items.values().find(|item| item.name == name)
+    }
+
+    /// First recipe held by a square crafting grid of `size` cells wide, read row by row
+    pub fn find_recipe(&self, grid: &[Option<ItemId>], size: u32) -> Option<&Recipe> {
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.matches(grid, size))
+    }
+}